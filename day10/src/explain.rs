@@ -0,0 +1,121 @@
+use crate::solver::{self, Goal, MachineSolver};
+use crate::{gf2, joltage, ParsedLine};
+use std::fmt::Write;
+
+/// A solver's answer for one machine written out press by press.
+pub struct Explanation {
+    pub text: String,
+    /// How many solutions share the optimum, if it could be counted
+    pub optimal_solutions: Option<u64>,
+}
+
+/// A button in the puzzle's notation, e.g. `(1,3)`.
+fn button(group: &[u8]) -> String {
+    let wired: Vec<String> = (0..group.len())
+        .filter(|&i| group[i] != 0)
+        .map(|i| i.to_string())
+        .collect();
+    format!("({})", wired.join(","))
+}
+
+fn state(goal: Goal, values: &[u64]) -> String {
+    match goal {
+        Goal::Lights => {
+            let lights: String = values
+                .iter()
+                .map(|v| if v % 2 == 0 { '.' } else { '#' })
+                .collect();
+            format!("[{}]", lights)
+        }
+        Goal::Joltage => {
+            let counters: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            format!("{{{}}}", counters.join(","))
+        }
+    }
+}
+
+/// The fewest presses and how many solutions reach it, found by the exact
+/// methods whichever backend is being explained.
+fn optimum(machine: &ParsedLine, goal: Goal) -> Option<(u64, u64)> {
+    match goal {
+        Goal::Lights => {
+            let (target, buttons) = solver::light_masks(machine).ok()?;
+            gf2::optimum(target, &buttons)
+                .ok()
+                .map(|(pressed, ties)| (pressed.len() as u64, ties))
+        }
+        Goal::Joltage => joltage::optimum(machine.target.as_deref()?, &machine.groups)
+            .ok()
+            .map(|(counts, ties)| (counts.iter().sum(), ties)),
+    }
+}
+
+/// Which buttons `solver` presses and how often, with the lights or counters
+/// after each button's presses, starting from everything at zero.
+pub fn explain(machine: &ParsedLine, goal: Goal, solver: &dyn MachineSolver) -> Explanation {
+    let mut text = String::new();
+    let counts = match solver.solve(machine, goal) {
+        Ok(counts) => counts,
+        Err(reason) => {
+            writeln!(
+                text,
+                "  {:?} with {}: impossible, {}",
+                goal,
+                solver.name(),
+                reason
+            )
+            .unwrap();
+            return Explanation {
+                text,
+                optimal_solutions: None,
+            };
+        }
+    };
+    writeln!(
+        text,
+        "  {:?} with {}: {} presses",
+        goal,
+        solver.name(),
+        counts.iter().sum::<u64>()
+    )
+    .unwrap();
+
+    let mut values = vec![0_u64; machine.pattern.len()];
+    writeln!(text, "    {}", state(goal, &values)).unwrap();
+    for (group, &count) in machine.groups.iter().zip(&counts) {
+        if count == 0 {
+            continue;
+        }
+        for (value, &wired) in values.iter_mut().zip(group) {
+            *value += wired as u64 * count;
+        }
+        writeln!(
+            text,
+            "    {} x{} -> {}",
+            button(group),
+            count,
+            state(goal, &values)
+        )
+        .unwrap();
+    }
+    if let Err(reason) = solver::verify(machine, goal, &counts) {
+        writeln!(text, "    WRONG: {}", reason).unwrap();
+    }
+
+    let optimum = optimum(machine, goal);
+    if let Some((fewest, _)) = optimum.filter(|&(fewest, _)| fewest < counts.iter().sum()) {
+        writeln!(text, "    NOT OPTIMAL: {} presses are enough", fewest).unwrap();
+    }
+    if let Some((fewest, ties)) = optimum.filter(|&(_, ties)| ties > 1) {
+        writeln!(
+            text,
+            "    NOT UNIQUE: {} ways to reach this with {} presses",
+            ties, fewest
+        )
+        .unwrap();
+    }
+    Explanation {
+        text,
+        optimal_solutions: optimum.map(|(_, ties)| ties),
+    }
+}
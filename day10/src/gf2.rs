@@ -0,0 +1,141 @@
+/// Most free buttons whose combinations `optimum` will walk through.
+const MAX_FREE_BUTTONS: usize = 31;
+
+/// Packs a 0/1 vector into a bitmask, entry `i` going to bit `i`.
+pub fn to_mask(bits: &[u8]) -> Result<u128, String> {
+    if bits.len() > 128 {
+        return Err(format!("{} lights don't fit in a 128-bit mask", bits.len()));
+    }
+    Ok(bits
+        .iter()
+        .enumerate()
+        .filter(|&(_, &bit)| bit != 0)
+        .fold(0, |mask, (i, _)| mask | 1 << i))
+}
+
+/// Fewest buttons that turn the lights from all off into `target`, with the
+/// indices of the buttons to press.
+///
+/// Pressing a button twice undoes it, so every button is pressed at most
+/// once and the answer is a solution of `A x = target` over GF(2), where
+/// column `j` of `A` is button `j`. Gaussian elimination gives one solution
+/// and a basis of the null space; every other solution is that one plus a
+/// combination of the basis, and those are walked in Gray code order so each
+/// step is a single XOR.
+pub fn fewest_presses(target: u128, buttons: &[u128]) -> Result<Vec<usize>, String> {
+    optimum(target, buttons).map(|(pressed, _)| pressed)
+}
+
+/// Like `fewest_presses`, also counting how many different sets of buttons
+/// reach the target with that few presses.
+pub fn optimum(target: u128, buttons: &[u128]) -> Result<(Vec<usize>, u64), String> {
+    if buttons.len() > 128 {
+        return Err(format!(
+            "{} buttons don't fit in a 128-bit mask",
+            buttons.len()
+        ));
+    }
+    let lights = 128
+        - buttons
+            .iter()
+            .fold(target, |acc, &b| acc | b)
+            .leading_zeros() as usize;
+
+    // Row `i` is light `i`: which buttons toggle it, and whether it ends up on
+    let mut rows: Vec<(u128, bool)> = (0..lights)
+        .map(|i| {
+            let toggled_by = buttons
+                .iter()
+                .enumerate()
+                .filter(|&(_, &b)| b >> i & 1 == 1)
+                .fold(0, |mask, (j, _)| mask | 1 << j);
+            (toggled_by, target >> i & 1 == 1)
+        })
+        .collect();
+
+    // Reduced row echelon form; `pivots[r]` is the button row `r` solves for
+    let mut pivots = Vec::new();
+    for col in 0..buttons.len() {
+        let rank = pivots.len();
+        let Some(found) = (rank..lights).find(|&r| rows[r].0 >> col & 1 == 1) else {
+            continue;
+        };
+        rows.swap(rank, found);
+        let pivot = rows[rank];
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != rank && row.0 >> col & 1 == 1 {
+                row.0 ^= pivot.0;
+                row.1 ^= pivot.1;
+            }
+        }
+        pivots.push(col);
+    }
+    // A leftover row reads 0 = 1
+    if rows[pivots.len()..].iter().any(|&(_, on)| on) {
+        return Err("the light pattern is not a sum of buttons over GF(2)".to_string());
+    }
+
+    // Free buttons pressed as zero
+    let mut pressed = pivots
+        .iter()
+        .zip(&rows)
+        .filter(|&(_, &(_, on))| on)
+        .fold(0_u128, |mask, (&col, _)| mask | 1 << col);
+
+    // Pressing free button `f` also flips every pivot button whose row uses it
+    let null_space: Vec<u128> = (0..buttons.len())
+        .filter(|col| !pivots.contains(col))
+        .map(|free| {
+            pivots
+                .iter()
+                .zip(&rows)
+                .filter(|&(_, &(mask, _))| mask >> free & 1 == 1)
+                .fold(1 << free, |mask, (&col, _)| mask | 1 << col)
+        })
+        .collect();
+    if null_space.len() > MAX_FREE_BUTTONS {
+        return Err(format!(
+            "{} free buttons, too many combinations to try",
+            null_space.len()
+        ));
+    }
+
+    let mut best = pressed;
+    let mut ties = 1;
+    for step in 1_u64..1 << null_space.len() {
+        pressed ^= null_space[step.trailing_zeros() as usize];
+        if pressed.count_ones() < best.count_ones() {
+            best = pressed;
+            ties = 1;
+        } else if pressed.count_ones() == best.count_ones() {
+            ties += 1;
+        }
+    }
+    let pressed = (0..buttons.len()).filter(|&j| best >> j & 1 == 1).collect();
+    Ok((pressed, ties))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights_beyond_64_bits() {
+        // Button `j` toggles lights `j` and `j + 1`, so light 99 on its own
+        // takes the buttons 0 to 98
+        let buttons: Vec<u128> = (0..99).map(|j| 0b11 << j).collect();
+        let (pressed, ties) = optimum(1 | 1 << 99, &buttons).unwrap();
+        assert_eq!(pressed, (0..99).collect::<Vec<_>>());
+        assert_eq!(ties, 1);
+    }
+
+    #[test]
+    fn limits_are_errors() {
+        assert!(to_mask(&[1; 128]).is_ok());
+        assert!(to_mask(&[1; 129]).is_err());
+        assert!(optimum(0, &[1; 129]).is_err());
+        // 40 copies of the same button leave 39 of them free
+        assert!(optimum(1, &[1; 40]).is_err());
+        assert!(optimum(2, &[1; 4]).is_err());
+    }
+}
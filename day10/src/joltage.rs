@@ -0,0 +1,348 @@
+/// Fewest button presses that raise the joltage counters from zero to
+/// exactly `target`, as the number of times each button is pressed.
+///
+/// `buttons[j][i]` is 1 if button `j` adds one to counter `i`. The counts
+/// solve `A x = target` in non-negative integers, where column `j` of `A` is
+/// button `j`. Gauss-Jordan elimination over the integers writes every pivot
+/// button in terms of the free ones, then the free buttons are searched
+/// exhaustively. No button can be pressed more often than the smallest
+/// counter it feeds, so the search is finite and an `Err` means there really
+/// is no way to reach the target.
+pub fn fewest_presses(target: &[u16], buttons: &[Vec<u8>]) -> Result<Vec<u64>, String> {
+    optimum(target, buttons).map(|(counts, _)| counts)
+}
+
+/// Like `fewest_presses`, also counting how many different press counts
+/// reach the target with that few presses in total.
+pub fn optimum(target: &[u16], buttons: &[Vec<u8>]) -> Result<(Vec<u64>, u64), String> {
+    let counters = target.len();
+    for (j, button) in buttons.iter().enumerate() {
+        if button.len() != counters {
+            return Err(format!(
+                "button {} is wired for {} counters but there are {}",
+                j,
+                button.len(),
+                counters
+            ));
+        }
+    }
+    for (i, &value) in target.iter().enumerate() {
+        if value > 0 && buttons.iter().all(|b| b[i] == 0) {
+            return Err(format!(
+                "no button feeds counter {}, which needs {}",
+                i, value
+            ));
+        }
+    }
+
+    let upper: Vec<i64> = buttons
+        .iter()
+        .map(|b| {
+            (0..counters)
+                .filter(|&i| b[i] != 0)
+                .map(|i| target[i] as i64)
+                .min()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let system = System::reduce(target, buttons)?;
+    let mut search = Search {
+        system: &system,
+        upper: &upper,
+        free_values: vec![0; system.free.len()],
+        best: None,
+        ties: 0,
+    };
+    search.run(0, 0);
+    let ties = search.ties;
+    let counts = search.best.ok_or_else(|| {
+        format!(
+            "no non-negative whole number of presses reaches {:?} ({} free buttons searched)",
+            target,
+            system.free.len()
+        )
+    })?;
+
+    // The elimination is exact, but check the answer against the machine anyway
+    for (i, &value) in target.iter().enumerate() {
+        let reached: u64 = buttons
+            .iter()
+            .zip(&counts)
+            .filter(|(b, _)| b[i] != 0)
+            .map(|(_, &count)| count)
+            .sum();
+        if reached != value as u64 {
+            return Err(format!(
+                "presses {:?} give counter {} = {} instead of {}",
+                counts, i, reached, value
+            ));
+        }
+    }
+    Ok((counts, ties))
+}
+
+/// The equations in reduced row echelon form. Row `r` reads
+/// `scale[r] * x[pivot[r]] + sum(coeffs[r][k] * x[free[k]]) = rhs[r]`.
+struct System {
+    pivot: Vec<usize>,
+    scale: Vec<i64>,
+    coeffs: Vec<Vec<i64>>,
+    rhs: Vec<i64>,
+    free: Vec<usize>,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl System {
+    fn reduce(target: &[u16], buttons: &[Vec<u8>]) -> Result<System, String> {
+        let width = buttons.len();
+        // One row per counter, the last column holds the target
+        let mut rows: Vec<Vec<i64>> = target
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let mut row: Vec<i64> = buttons.iter().map(|b| b[i] as i64).collect();
+                row.push(value as i64);
+                row
+            })
+            .collect();
+
+        let mut pivot = Vec::new();
+        for col in 0..width {
+            let rank = pivot.len();
+            let Some(found) = (rank..rows.len()).find(|&r| rows[r][col] != 0) else {
+                continue;
+            };
+            rows.swap(rank, found);
+            let pivot_row = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r == rank || row[col] == 0 {
+                    continue;
+                }
+                // Fraction free: scale both rows so the column cancels
+                let factor = row[col];
+                for (value, &p) in row.iter_mut().zip(&pivot_row) {
+                    *value = *value * pivot_row[col] - factor * p;
+                }
+                let divisor = row.iter().fold(0, |g, &v| gcd(g, v));
+                if divisor > 1 {
+                    row.iter_mut().for_each(|v| *v /= divisor);
+                }
+            }
+            pivot.push(col);
+        }
+
+        if let Some(row) = rows[pivot.len()..].iter().find(|row| row[width] != 0) {
+            return Err(format!(
+                "the counters contradict each other: a combination of them needs 0 = {}",
+                row[width]
+            ));
+        }
+
+        let free: Vec<usize> = (0..width).filter(|col| !pivot.contains(col)).collect();
+        let rank = pivot.len();
+        Ok(System {
+            scale: (0..rank).map(|r| rows[r][pivot[r]]).collect(),
+            coeffs: (0..rank)
+                .map(|r| free.iter().map(|&f| rows[r][f]).collect())
+                .collect(),
+            rhs: (0..rank).map(|r| rows[r][width]).collect(),
+            pivot,
+            free,
+        })
+    }
+}
+
+/// Depth-first search over the free buttons' press counts.
+struct Search<'a> {
+    system: &'a System,
+    upper: &'a [i64],
+    free_values: Vec<i64>,
+    best: Option<Vec<u64>>,
+    /// Solutions found with the same total as `best`
+    ties: u64,
+}
+
+impl Search<'_> {
+    fn best_total(&self) -> Option<u64> {
+        self.best.as_ref().map(|counts| counts.iter().sum())
+    }
+
+    fn run(&mut self, k: usize, presses_so_far: i64) {
+        // Pivot buttons can only add presses
+        if self
+            .best_total()
+            .is_some_and(|best| presses_so_far as u64 > best)
+        {
+            return;
+        }
+        if k < self.free_values.len() {
+            for value in 0..=self.upper[self.system.free[k]] {
+                self.free_values[k] = value;
+                self.run(k + 1, presses_so_far + value);
+            }
+            return;
+        }
+
+        let system = self.system;
+        let mut counts = vec![0_u64; self.upper.len()];
+        for (&col, &value) in system.free.iter().zip(&self.free_values) {
+            counts[col] = value as u64;
+        }
+        for r in 0..system.pivot.len() {
+            let rest: i64 = system.coeffs[r]
+                .iter()
+                .zip(&self.free_values)
+                .map(|(&c, &v)| c * v)
+                .sum();
+            let numerator = system.rhs[r] - rest;
+            if numerator % system.scale[r] != 0 {
+                return;
+            }
+            let value = numerator / system.scale[r];
+            if value < 0 || value > self.upper[system.pivot[r]] {
+                return;
+            }
+            counts[system.pivot[r]] = value as u64;
+        }
+        let total: u64 = counts.iter().sum();
+        match self.best_total() {
+            Some(best) if total > best => {}
+            Some(best) if total == best => self.ties += 1,
+            _ => {
+                self.best = Some(counts);
+                self.ties = 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Buttons given as the counters they feed, as 0/1 rows.
+    fn wired(counters: usize, buttons: &[&[usize]]) -> Vec<Vec<u8>> {
+        buttons
+            .iter()
+            .map(|fed| (0..counters).map(|i| fed.contains(&i) as u8).collect())
+            .collect()
+    }
+
+    /// Targets, the counters each button feeds, and the fewest presses.
+    type Machine = (&'static [u16], &'static [&'static [usize]], u64);
+
+    fn reached(buttons: &[Vec<u8>], counts: &[u64]) -> Vec<u64> {
+        (0..buttons[0].len())
+            .map(|i| {
+                buttons
+                    .iter()
+                    .zip(counts)
+                    .map(|(b, &c)| b[i] as u64 * c)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let machines: [Machine; 4] = [
+            (
+                &[3, 5, 4, 7],
+                &[&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]],
+                10,
+            ),
+            (
+                &[7, 5, 12, 7, 2],
+                &[&[0, 2, 3, 4], &[2, 3], &[0, 4], &[0, 1, 2], &[1, 2, 3, 4]],
+                12,
+            ),
+            (
+                &[10, 11, 11, 5, 10, 5],
+                &[&[0, 1, 2, 3, 4], &[0, 3, 4], &[0, 1, 2, 4, 5], &[1, 2]],
+                11,
+            ),
+            // Big enough that minilp settles for 118 presses
+            (
+                &[35, 44, 107, 74, 41, 44, 31, 81],
+                &[
+                    &[0, 2, 6, 7],
+                    &[2, 3],
+                    &[1, 2, 3, 5, 7],
+                    &[0, 1, 3, 4, 5, 6, 7],
+                    &[0, 1, 2, 3, 5, 7],
+                    &[0, 2, 4, 5, 7],
+                    &[1, 2, 6, 7],
+                    &[0, 2, 3, 5],
+                    &[3, 7],
+                    &[2, 3, 4],
+                ],
+                114,
+            ),
+        ];
+        for (target, fed, fewest) in machines {
+            let buttons = wired(target.len(), fed);
+            let counts = fewest_presses(target, &buttons).unwrap();
+            let expected: Vec<u64> = target.iter().map(|&t| t as u64).collect();
+            assert_eq!(reached(&buttons, &counts), expected);
+            assert_eq!(counts.iter().sum::<u64>(), fewest);
+        }
+    }
+
+    /// Fewest presses over every combination of up to 6 presses per button,
+    /// and how many combinations reach it.
+    fn brute_force(target: &[u16], buttons: &[Vec<u8>]) -> Option<(u64, u64)> {
+        let expected: Vec<u64> = target.iter().map(|&t| t as u64).collect();
+        let mut best: Option<(u64, u64)> = None;
+        let mut counts = vec![0_u64; buttons.len()];
+        loop {
+            if reached(buttons, &counts) == expected {
+                let total = counts.iter().sum();
+                best = match best {
+                    Some((fewest, ties)) if total == fewest => Some((fewest, ties + 1)),
+                    Some((fewest, _)) if total > fewest => best,
+                    _ => Some((total, 1)),
+                };
+            }
+            let Some(j) = counts.iter().position(|&c| c < 6) else {
+                return best;
+            };
+            counts[j] += 1;
+            counts[..j].fill(0);
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut seed = 7_u64;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..300 {
+            let counters = 2 + next(3) as usize;
+            let buttons: Vec<Vec<u8>> = (0..2 + next(4))
+                .map(|_| (0..counters).map(|_| next(2) as u8).collect())
+                .collect();
+            let target: Vec<u16> = (0..counters).map(|_| next(7) as u16).collect();
+            let found =
+                optimum(&target, &buttons).map(|(counts, ties)| (counts.iter().sum(), ties));
+            assert_eq!(
+                found.ok(),
+                brute_force(&target, &buttons),
+                "{:?} {:?}",
+                target,
+                buttons
+            );
+        }
+    }
+}
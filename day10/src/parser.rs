@@ -0,0 +1,284 @@
+use crate::ParsedLine;
+use std::fmt;
+use std::str::FromStr;
+
+/// What went wrong where, with the column counted in characters from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open(char),
+    Close(char),
+    Comma,
+    Light(u8),
+    Number(u64),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Open(c) | Token::Close(c) => write!(f, "'{}'", c),
+            Token::Comma => write!(f, "','"),
+            Token::Light(0) => write!(f, "'.'"),
+            Token::Light(_) => write!(f, "'#'"),
+            Token::Number(n) => write!(f, "number {}", n),
+        }
+    }
+}
+
+fn error<T>(column: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        column,
+        message: message.into(),
+    })
+}
+
+/// Splits a line into tokens and their columns, skipping whitespace.
+fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().enumerate().peekable();
+    while let Some((i, c)) = chars.next() {
+        let column = i + 1;
+        let token = match c {
+            '[' | '(' | '{' => Token::Open(c),
+            ']' | ')' | '}' => Token::Close(c),
+            ',' => Token::Comma,
+            '.' => Token::Light(0),
+            '#' => Token::Light(1),
+            '0'..='9' => {
+                let mut digits = c.to_string();
+                while let Some(&(_, d)) = chars.peek().filter(|(_, d)| d.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                match digits.parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => return error(column, format!("number {} is too large", digits)),
+                }
+            }
+            _ if c.is_whitespace() => continue,
+            _ => return error(column, format!("unexpected character {:?}", c)),
+        };
+        tokens.push((column, token));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over `[pattern] (a,b) ... {targets}`, where the
+/// joltage targets are optional.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// Column just past the end of the line, for "expected ..." at the end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).map(|&(_, token)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |&(column, _)| column)
+    }
+
+    fn expect(&mut self, wanted: Token) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(token) if token == wanted => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(token) => error(
+                self.column(),
+                format!("expected {}, found {}", wanted, token),
+            ),
+            None => error(
+                self.column(),
+                format!("expected {}, found end of line", wanted),
+            ),
+        }
+    }
+
+    /// `n (, n)*` up to and including `close`, with each number's column.
+    fn numbers(&mut self, close: char) -> Result<Vec<(usize, u64)>, ParseError> {
+        let mut numbers = Vec::new();
+        loop {
+            let column = self.column();
+            match self.peek() {
+                Some(Token::Number(n)) => {
+                    self.position += 1;
+                    numbers.push((column, n));
+                }
+                Some(token) => return error(column, format!("expected a number, found {}", token)),
+                None => return error(column, "expected a number, found end of line"),
+            }
+            match self.peek() {
+                Some(Token::Comma) => self.position += 1,
+                _ => return self.expect(Token::Close(close)).map(|_| numbers),
+            }
+        }
+    }
+
+    fn machine(&mut self) -> Result<ParsedLine, ParseError> {
+        self.expect(Token::Open('['))?;
+        let mut pattern = Vec::new();
+        while let Some(Token::Light(light)) = self.peek() {
+            self.position += 1;
+            pattern.push(light);
+        }
+        self.expect(Token::Close(']'))?;
+        if pattern.is_empty() {
+            return error(self.column(), "the light pattern is empty");
+        }
+
+        let mut groups = Vec::new();
+        while self.peek() == Some(Token::Open('(')) {
+            self.position += 1;
+            let mut group = vec![0; pattern.len()];
+            for (column, index) in self.numbers(')')? {
+                if index >= pattern.len() as u64 {
+                    return error(
+                        column,
+                        format!(
+                            "button wired to light {} but there are only {} lights",
+                            index,
+                            pattern.len()
+                        ),
+                    );
+                }
+                if group[index as usize] == 1 {
+                    return error(column, format!("light {} appears twice in a button", index));
+                }
+                group[index as usize] = 1;
+            }
+            groups.push(group);
+        }
+
+        let mut target = None;
+        if self.peek() == Some(Token::Open('{')) {
+            let open = self.column();
+            self.position += 1;
+            let mut values = Vec::new();
+            for (column, value) in self.numbers('}')? {
+                match u16::try_from(value) {
+                    Ok(value) => values.push(value),
+                    Err(_) => return error(column, format!("joltage {} is too large", value)),
+                }
+            }
+            if values.len() != pattern.len() {
+                return error(
+                    open,
+                    format!(
+                        "{} joltage targets for {} lights",
+                        values.len(),
+                        pattern.len()
+                    ),
+                );
+            }
+            target = Some(values);
+        }
+
+        if let Some(token) = self.peek() {
+            return error(
+                self.column(),
+                format!("unexpected {} after the machine", token),
+            );
+        }
+        Ok(ParsedLine {
+            pattern,
+            groups,
+            target,
+        })
+    }
+}
+
+impl FromStr for ParsedLine {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(line)?,
+            position: 0,
+            end: line.chars().count() + 1,
+        };
+        parser.machine()
+    }
+}
+
+/// Writes the machine back in the puzzle's notation, which parses to the
+/// same machine again.
+impl fmt::Display for ParsedLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lights: String = self
+            .pattern
+            .iter()
+            .map(|&light| if light == 0 { '.' } else { '#' })
+            .collect();
+        write!(f, "[{}]", lights)?;
+        for group in &self.groups {
+            let wired: Vec<String> = (0..group.len())
+                .filter(|&i| group[i] != 0)
+                .map(|i| i.to_string())
+                .collect();
+            write!(f, " ({})", wired.join(","))?;
+        }
+        if let Some(target) = &self.target {
+            let values: Vec<String> = target.iter().map(|v| v.to_string()).collect();
+            write!(f, " {{{}}}", values.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_of(line: &str) -> usize {
+        line.parse::<ParsedLine>().unwrap_err().column
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_column() {
+        assert_eq!(column_of("[.#] (0) x"), 10);
+        assert_eq!(column_of("[.#] (0,2) {1,1}"), 9);
+        assert_eq!(column_of("[.#] (1,1)"), 9);
+        assert_eq!(column_of("[.#] (0 {1,1}"), 9);
+        assert_eq!(column_of("[.#] (0) {1,70000}"), 13);
+        assert_eq!(column_of("[.#] (0) {1}"), 10);
+        assert_eq!(column_of("[.#] (0"), 8);
+        assert_eq!(column_of("[] (0)"), 4);
+        assert_eq!(column_of("[.#] (0) {1,1} (1)"), 16);
+    }
+
+    #[test]
+    fn display_parses_back_to_the_same_machine() {
+        let lines = [
+            "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}",
+            "[...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}",
+            "[#] (0)",
+            "[..]",
+        ];
+        for line in lines {
+            let machine: ParsedLine = line.parse().unwrap();
+            assert_eq!(machine.to_string(), line);
+            assert_eq!(machine.to_string().parse::<ParsedLine>(), Ok(machine));
+        }
+
+        // Spacing and the order of a button's lights aren't kept
+        let machine: ParsedLine = "[.#]( 1 ,0 ){ 2, 3 }".parse().unwrap();
+        assert_eq!(machine.to_string(), "[.#] (0,1) {2,3}");
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{gf2, joltage, ParsedLine};
+use good_lp::*;
+use std::collections::{HashMap, VecDeque};
+use std::panic;
+
+/// What a machine has to be configured for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Part 1: toggle the indicator lights into the `[...]` pattern
+    Lights,
+    /// Part 2: raise the joltage counters to exactly the `{...}` values
+    Joltage,
+}
+
+/// A way of finding the fewest button presses for one machine.
+pub trait MachineSolver {
+    fn name(&self) -> &'static str;
+
+    fn supports(&self, goal: Goal) -> bool;
+
+    /// How often each button is pressed in a solution with the fewest
+    /// presses, or why there isn't one.
+    fn solve(&self, machine: &ParsedLine, goal: Goal) -> Result<Vec<u64>, String>;
+}
+
+/// Breadth-first search over light states. Only practical for a handful of
+/// lights, but it doesn't rely on any algebra.
+pub struct Bfs;
+
+/// Gaussian elimination over GF(2), see `gf2::fewest_presses`.
+pub struct Gf2;
+
+/// Integer linear program handed to good_lp's minilp backend.
+pub struct Ilp;
+
+/// Exact integer search, see `joltage::fewest_presses`.
+pub struct Exact;
+
+/// Every backend, in the order cross-checking runs them.
+pub fn all() -> Vec<Box<dyn MachineSolver>> {
+    vec![Box::new(Bfs), Box::new(Gf2), Box::new(Ilp), Box::new(Exact)]
+}
+
+pub fn by_name(name: &str) -> Result<Box<dyn MachineSolver>, String> {
+    all().into_iter().find(|s| s.name() == name).ok_or_else(|| {
+        format!(
+            "unknown solver {:?}, expected one of bfs, gf2, ilp or exact",
+            name
+        )
+    })
+}
+
+/// Refuses goals a backend wasn't written for, rather than quietly solving
+/// the other one.
+fn check_goal(solver: &dyn MachineSolver, goal: Goal) -> Result<(), String> {
+    if solver.supports(goal) {
+        Ok(())
+    } else {
+        Err(format!("{} does not support {:?}", solver.name(), goal))
+    }
+}
+
+fn joltage_target(machine: &ParsedLine) -> Result<&[u16], String> {
+    machine
+        .target
+        .as_deref()
+        .ok_or_else(|| "the machine has no joltage targets".to_string())
+}
+
+/// The light pattern and the buttons as bitmasks.
+pub fn light_masks(machine: &ParsedLine) -> Result<(u128, Vec<u128>), String> {
+    let target = gf2::to_mask(&machine.pattern)?;
+    let buttons = machine
+        .groups
+        .iter()
+        .map(|g| gf2::to_mask(g))
+        .collect::<Result<_, _>>()?;
+    Ok((target, buttons))
+}
+
+/// Checks that pressing the buttons `counts` times does reach the goal.
+pub fn verify(machine: &ParsedLine, goal: Goal, counts: &[u64]) -> Result<(), String> {
+    if counts.len() != machine.groups.len() {
+        return Err(format!(
+            "{} press counts for {} buttons",
+            counts.len(),
+            machine.groups.len()
+        ));
+    }
+    let reached: Vec<u64> = (0..machine.pattern.len())
+        .map(|i| {
+            machine
+                .groups
+                .iter()
+                .zip(counts)
+                .filter(|(group, _)| group[i] != 0)
+                .map(|(_, &count)| count)
+                .sum()
+        })
+        .collect();
+    let expected: Vec<u64> = match goal {
+        Goal::Lights => {
+            let reached: Vec<u64> = reached.iter().map(|r| r % 2).collect();
+            let expected: Vec<u64> = machine.pattern.iter().map(|&p| p as u64).collect();
+            if reached != expected {
+                return Err(format!("lights end up {:?}, not {:?}", reached, expected));
+            }
+            return Ok(());
+        }
+        Goal::Joltage => joltage_target(machine)?.iter().map(|&t| t as u64).collect(),
+    };
+    if reached != expected {
+        return Err(format!("counters end up {:?}, not {:?}", reached, expected));
+    }
+    Ok(())
+}
+
+impl MachineSolver for Bfs {
+    fn name(&self) -> &'static str {
+        "bfs"
+    }
+
+    fn supports(&self, goal: Goal) -> bool {
+        goal == Goal::Lights
+    }
+
+    fn solve(&self, machine: &ParsedLine, goal: Goal) -> Result<Vec<u64>, String> {
+        check_goal(self, goal)?;
+        let (target, buttons) = light_masks(machine)?;
+
+        // Each state remembers the state before it and the button pressed
+        let mut came_from: HashMap<u128, (u128, usize)> = HashMap::new();
+        let mut queue = VecDeque::from([0_u128]);
+        came_from.insert(0, (0, usize::MAX));
+        while let Some(current) = queue.pop_front() {
+            if current == target {
+                let mut counts = vec![0; buttons.len()];
+                let mut state = current;
+                while state != 0 {
+                    let (previous, button) = came_from[&state];
+                    counts[button] += 1;
+                    state = previous;
+                }
+                return Ok(counts);
+            }
+            for (j, &button) in buttons.iter().enumerate() {
+                let next = current ^ button;
+                came_from.entry(next).or_insert_with(|| {
+                    queue.push_back(next);
+                    (current, j)
+                });
+            }
+        }
+        Err("no combination of buttons gives the light pattern".to_string())
+    }
+}
+
+impl MachineSolver for Gf2 {
+    fn name(&self) -> &'static str {
+        "gf2"
+    }
+
+    fn supports(&self, goal: Goal) -> bool {
+        goal == Goal::Lights
+    }
+
+    fn solve(&self, machine: &ParsedLine, goal: Goal) -> Result<Vec<u64>, String> {
+        check_goal(self, goal)?;
+        let (target, buttons) = light_masks(machine)?;
+        let pressed = gf2::fewest_presses(target, &buttons)?;
+        let mut counts = vec![0; buttons.len()];
+        for j in pressed {
+            counts[j] = 1;
+        }
+        Ok(counts)
+    }
+}
+
+impl MachineSolver for Ilp {
+    fn name(&self) -> &'static str {
+        "ilp"
+    }
+
+    fn supports(&self, goal: Goal) -> bool {
+        goal == Goal::Joltage
+    }
+
+    fn solve(&self, machine: &ParsedLine, goal: Goal) -> Result<Vec<u64>, String> {
+        check_goal(self, goal)?;
+        let target = joltage_target(machine)?;
+        let instructions = &machine.groups;
+        let mut problem = ProblemVariables::new();
+
+        // Create integer variables for each instruction (how many times to use it)
+        let usage_vars: Vec<Variable> = (0..instructions.len())
+            .map(|i| problem.add(variable().integer().min(0).name(format!("x{}", i))))
+            .collect();
+
+        // Objective: minimize the sum of all usage variables
+        let objective: Expression = usage_vars
+            .iter()
+            .fold(Expression::from(0.0), |acc, &var| acc + var);
+
+        // Build the solver
+        let mut solver = problem.minimise(objective).using(default_solver);
+
+        // Add constraints for each target position
+        for (pos_idx, &target_val) in target.iter().enumerate() {
+            let mut expr = Expression::from(0.0);
+
+            for (instr_idx, instruction) in instructions.iter().enumerate() {
+                let coeff = instruction[pos_idx] as f64;
+                if coeff > 0.0 {
+                    expr += coeff * usage_vars[instr_idx];
+                }
+            }
+
+            // Constraint: sum must equal target value
+            solver = solver.with(constraint!(expr == target_val as f64));
+        }
+
+        // minilp can panic while adding cutting planes, which shouldn't take
+        // the other machines down with it. The panic message still goes to
+        // stderr through whatever hook is installed.
+        let outcome = panic::catch_unwind(panic::AssertUnwindSafe(|| solver.solve()));
+        let solution = outcome
+            .map_err(|_| "minilp panicked".to_string())?
+            .map_err(|e| format!("minilp: {}", e))?;
+        Ok(usage_vars
+            .iter()
+            .map(|&var| solution.value(var).round().max(0.0) as u64)
+            .collect())
+    }
+}
+
+impl MachineSolver for Exact {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn supports(&self, goal: Goal) -> bool {
+        goal == Goal::Joltage
+    }
+
+    fn solve(&self, machine: &ParsedLine, goal: Goal) -> Result<Vec<u64>, String> {
+        check_goal(self, goal)?;
+        joltage::fewest_presses(joltage_target(machine)?, &machine.groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> ParsedLine {
+        "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn unsupported_goals_are_errors() {
+        for solver in all() {
+            for goal in [Goal::Lights, Goal::Joltage] {
+                let result = solver.solve(&machine(), goal);
+                if solver.supports(goal) {
+                    // minilp fails on this one, see the cross-check
+                    if let Ok(counts) = result {
+                        assert_eq!(verify(&machine(), goal, &counts), Ok(()));
+                    }
+                } else {
+                    assert!(result.unwrap_err().contains("does not support"));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::paths::{PathCounter, PathError};
+use petgraph::Direction;
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Every path from the source to the target, produced one at a time by an
+/// explicit-stack depth-first search. Only nodes that lie on some such path
+/// are entered, so each step down the stack leads to at least one answer.
+pub struct Paths<'a, N, E> {
+    graph: &'a DiGraph<N, E>,
+    target: NodeIndex,
+    between: Vec<bool>,
+    /// The current path, each node with the index of its next edge to try
+    stack: Vec<(NodeIndex, usize)>,
+}
+
+impl<'a, N, E> Paths<'a, N, E> {
+    pub fn new(
+        counter: &PathCounter<'a, N, E>,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Result<Self, PathError> {
+        let between = counter.between(source, target, &[])?;
+        let stack = if between[source.index()] {
+            vec![(source, 0)]
+        } else {
+            Vec::new()
+        };
+        Ok(Paths {
+            graph: counter.graph(),
+            target,
+            between,
+            stack,
+        })
+    }
+}
+
+impl<N, E> Iterator for Paths<'_, N, E> {
+    type Item = Vec<NodeIndex>;
+
+    fn next(&mut self) -> Option<Vec<NodeIndex>> {
+        while let Some(&mut (node, ref mut tried)) = self.stack.last_mut() {
+            if node == self.target {
+                let path = self.stack.iter().map(|&(n, _)| n).collect();
+                self.stack.pop();
+                return Some(path);
+            }
+            let next = self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .skip(*tried)
+                .find(|next| {
+                    *tried += 1;
+                    self.between[next.index()]
+                });
+            match next {
+                Some(next) => self.stack.push((next, 0)),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The `k` paths with the fewest edges, shortest first. A best-first search
+/// over partial paths, ranked by their length plus the exact distance still
+/// to go, so complete paths come off the heap in order.
+pub fn k_shortest<N, E>(
+    counter: &PathCounter<N, E>,
+    source: NodeIndex,
+    target: NodeIndex,
+    k: usize,
+) -> Result<Vec<Vec<NodeIndex>>, PathError> {
+    let graph = counter.graph();
+    let between = counter.between(source, target, &[])?;
+    if !between[source.index()] {
+        return Ok(Vec::new());
+    }
+
+    // Edges left to the target, by breadth-first search backwards
+    let mut to_go = vec![usize::MAX; graph.node_count()];
+    to_go[target.index()] = 0;
+    let mut queue = VecDeque::from([target]);
+    while let Some(node) = queue.pop_front() {
+        for previous in graph.neighbors_directed(node, Direction::Incoming) {
+            if between[previous.index()] && to_go[previous.index()] == usize::MAX {
+                to_go[previous.index()] = to_go[node.index()] + 1;
+                queue.push_back(previous);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut heap = BinaryHeap::from([Reverse((to_go[source.index()], vec![source]))]);
+    while let Some(Reverse((_, path))) = heap.pop() {
+        let last = *path.last().unwrap();
+        if last == target {
+            found.push(path);
+            if found.len() == k {
+                break;
+            }
+            continue;
+        }
+        for next in graph.neighbors_directed(last, Direction::Outgoing) {
+            if between[next.index()] {
+                let mut longer = path.clone();
+                longer.push(next);
+                heap.push(Reverse((longer.len() - 1 + to_go[next.index()], longer)));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// SplitMix64, enough randomness to pick paths without another dependency.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, rejecting the top sliver that would bias `%`.
+    fn below(&mut self, bound: u128) -> u128 {
+        let zone = u128::MAX - u128::MAX % bound;
+        loop {
+            let value = (self.next_u64() as u128) << 64 | self.next_u64() as u128;
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Draws paths uniformly at random among all paths from the source to the
+/// target. Each step picks a successor with probability proportional to the
+/// number of paths that continue through it.
+pub struct PathSampler<'a, N, E> {
+    graph: &'a DiGraph<N, E>,
+    source: NodeIndex,
+    target: NodeIndex,
+    counts: Vec<u128>,
+}
+
+impl<'a, N, E> PathSampler<'a, N, E> {
+    pub fn new(
+        counter: &PathCounter<'a, N, E>,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Result<Self, PathError> {
+        Ok(PathSampler {
+            graph: counter.graph(),
+            source,
+            target,
+            counts: counter.path_counts(source, target)?,
+        })
+    }
+
+    /// `None` if there is no path at all.
+    pub fn sample(&self, rng: &mut SplitMix64) -> Option<Vec<NodeIndex>> {
+        if self.counts[self.source.index()] == 0 {
+            return None;
+        }
+        let mut path = vec![self.source];
+        let mut node = self.source;
+        while node != self.target {
+            // The paths through the successors add up to this node's count
+            let mut pick = rng.below(self.counts[node.index()]);
+            node = self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .find(|next| {
+                    let through = self.counts[next.index()];
+                    if pick < through {
+                        true
+                    } else {
+                        pick -= through;
+                        false
+                    }
+                })
+                .expect("successor counts add up to the node's count");
+            path.push(node);
+        }
+        Some(path)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::graph::LabeledGraph;
+use crate::paths::PathCounter;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::fmt::Write;
+
+/// Which devices lie on a path from one of the start nodes to the target.
+/// The rest are dead: either no start reaches them or they never get to the
+/// target, so they can't affect any path count.
+pub struct Reachability {
+    pub starts: Vec<NodeIndex>,
+    pub target: NodeIndex,
+    pub from_start: Vec<bool>,
+    pub to_target: Vec<bool>,
+}
+
+impl Reachability {
+    pub fn new(counter: &PathCounter<String, ()>, starts: &[NodeIndex], target: NodeIndex) -> Self {
+        let mut from_start = vec![false; counter.node_count()];
+        for &start in starts {
+            for (seen, reached) in from_start.iter_mut().zip(counter.reachable_from(start)) {
+                *seen |= reached;
+            }
+        }
+        Reachability {
+            starts: starts.to_vec(),
+            target,
+            from_start,
+            to_target: counter.reaching(target),
+        }
+    }
+
+    pub fn on_path(&self, node: NodeIndex) -> bool {
+        self.from_start[node.index()] && self.to_target[node.index()]
+    }
+
+    /// Both ends being on a path puts the edge on one too: the tail is
+    /// reachable from a start and the head reaches the target.
+    fn edge_on_path(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.on_path(from) && self.on_path(to)
+    }
+
+    /// Printable summary of the dead nodes, grouped by why they're dead.
+    pub fn dead_report(&self, devices: &LabeledGraph) -> String {
+        let nodes = devices.graph().node_indices();
+        let unreached: Vec<&str> = nodes
+            .clone()
+            .filter(|n| !self.from_start[n.index()])
+            .map(|n| devices.name(n))
+            .collect();
+        let stuck: Vec<&str> = nodes
+            .filter(|n| self.from_start[n.index()] && !self.to_target[n.index()])
+            .map(|n| devices.name(n))
+            .collect();
+        let starts: Vec<&str> = self.starts.iter().map(|&n| devices.name(n)).collect();
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{} of {} devices are on a path from {} to {}",
+            devices.graph().node_count() - unreached.len() - stuck.len(),
+            devices.graph().node_count(),
+            starts.join(" or "),
+            devices.name(self.target)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{} unreachable from {}: {}",
+            unreached.len(),
+            starts.join(" or "),
+            unreached.join(" ")
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{} reachable but can't get to {}: {}",
+            stuck.len(),
+            devices.name(self.target),
+            stuck.join(" ")
+        )
+        .unwrap();
+        out
+    }
+
+    /// GraphViz digraph with the path subgraph in red and dead nodes greyed out.
+    pub fn to_dot(&self, devices: &LabeledGraph) -> String {
+        let graph = devices.graph();
+        let mut out = String::from("digraph devices {\n");
+        for node in graph.node_indices() {
+            let style = if self.starts.contains(&node) || node == self.target {
+                "color=red, penwidth=2, shape=doublecircle"
+            } else if self.on_path(node) {
+                "color=red"
+            } else {
+                "color=grey, fontcolor=grey, style=dashed"
+            };
+            writeln!(
+                out,
+                "  \"{}\" [{}];",
+                escape_quoted(devices.name(node)),
+                style
+            )
+            .unwrap();
+        }
+        for edge in graph.edge_references() {
+            let style = if self.edge_on_path(edge.source(), edge.target()) {
+                "color=red"
+            } else {
+                "color=grey"
+            };
+            writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [{}];",
+                escape_quoted(devices.name(edge.source())),
+                escape_quoted(devices.name(edge.target())),
+                style
+            )
+            .unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_graphml(&self, devices: &LabeledGraph) -> String {
+        let graph = devices.graph();
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+            "  <key id=\"on_path\" for=\"all\" attr.name=\"on_path\" attr.type=\"boolean\"/>\n",
+            "  <graph id=\"devices\" edgedefault=\"directed\">\n",
+        ));
+        for node in graph.node_indices() {
+            writeln!(
+                out,
+                "    <node id=\"n{}\"><data key=\"name\">{}</data><data key=\"on_path\">{}</data></node>",
+                node.index(),
+                escape_xml(devices.name(node)),
+                self.on_path(node)
+            )
+            .unwrap();
+        }
+        for edge in graph.edge_references() {
+            writeln!(
+                out,
+                "    <edge source=\"n{}\" target=\"n{}\"><data key=\"on_path\">{}</data></edge>",
+                edge.source().index(),
+                edge.target().index(),
+                self.edge_on_path(edge.source(), edge.target())
+            )
+            .unwrap();
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    pub fn to_json(&self, devices: &LabeledGraph) -> String {
+        let graph = devices.graph();
+        let nodes: Vec<String> = graph
+            .node_indices()
+            .map(|node| {
+                format!(
+                    "    {{\"id\": {}, \"name\": \"{}\", \"from_start\": {}, \"to_target\": {}, \"on_path\": {}}}",
+                    node.index(),
+                    escape_json(devices.name(node)),
+                    self.from_start[node.index()],
+                    self.to_target[node.index()],
+                    self.on_path(node)
+                )
+            })
+            .collect();
+        let edges: Vec<String> = graph
+            .edge_references()
+            .map(|edge| {
+                format!(
+                    "    {{\"source\": {}, \"target\": {}, \"on_path\": {}}}",
+                    edge.source().index(),
+                    edge.target().index(),
+                    self.edge_on_path(edge.source(), edge.target())
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+}
+
+/// Backslashes and double quotes escaped, for a DOT quoted ID.
+fn escape_quoted(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The same plus control characters, which a JSON string can't hold raw.
+fn escape_json(text: &str) -> String {
+    escape_quoted(text)
+        .chars()
+        .map(|c| {
+            if c.is_control() {
+                format!("\\u{:04x}", c as u32)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_escaped() {
+        let devices = LabeledGraph::builder()
+            .sink("out")
+            .parse("you: a\"b\na\"b: c\\d\nc\\d: out\n")
+            .unwrap();
+        let counter = PathCounter::new(devices.graph());
+        let you = devices.node("you").unwrap();
+        let out = devices.node("out").unwrap();
+        let reach = Reachability::new(&counter, &[you], out);
+
+        let dot = reach.to_dot(&devices);
+        assert!(dot.contains(r#"  "a\"b" -> "c\\d" [color=red];"#));
+        let json = reach.to_json(&devices);
+        assert!(json.contains(r#""name": "a\"b""#));
+        assert!(json.contains(r#""name": "c\\d""#));
+        let graphml = reach.to_graphml(&devices);
+        assert!(graphml.contains("<data key=\"name\">a&quot;b</data>"));
+    }
+}
@@ -0,0 +1,168 @@
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Problems with a device list, with 1-based line numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    DuplicateDefinition {
+        name: String,
+        first: usize,
+        again: usize,
+    },
+    DuplicateTarget {
+        name: String,
+        target: String,
+        line: usize,
+    },
+    DanglingTarget {
+        name: String,
+        line: usize,
+    },
+    UnknownNode(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            GraphError::DuplicateDefinition { name, first, again } => write!(
+                f,
+                "line {}: {} was already defined on line {}",
+                again, name, first
+            ),
+            GraphError::DuplicateTarget { name, target, line } => {
+                write!(f, "line {}: {} lists {} twice", line, name, target)
+            }
+            GraphError::DanglingTarget { name, line } => write!(
+                f,
+                "line {}: {} is never defined and isn't a known sink",
+                line, name
+            ),
+            GraphError::UnknownNode(name) => write!(f, "no device called {}", name),
+        }
+    }
+}
+
+/// A petgraph graph whose nodes carry their device names, with lookup the
+/// other way round.
+pub struct LabeledGraph {
+    graph: DiGraph<String, ()>,
+    index: HashMap<String, NodeIndex>,
+}
+
+/// Reads the `src: dst dst ...` format. Every node has to be defined on a
+/// line of its own unless it was declared a sink.
+#[derive(Default)]
+pub struct GraphBuilder {
+    sinks: Vec<String>,
+}
+
+impl GraphBuilder {
+    /// Lets `name` appear as a target without a line of its own.
+    pub fn sink(mut self, name: &str) -> Self {
+        self.sinks.push(name.to_string());
+        self
+    }
+
+    pub fn parse(self, text: &str) -> Result<LabeledGraph, GraphError> {
+        let mut labeled = LabeledGraph {
+            graph: DiGraph::new(),
+            index: HashMap::new(),
+        };
+        let mut defined_on: HashMap<&str, usize> = HashMap::new();
+        let mut first_use: Vec<(&str, usize)> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (source, targets) = line.split_once(':').ok_or_else(|| GraphError::Syntax {
+                line: number,
+                message: "expected `name: target target ...`".to_string(),
+            })?;
+            let source = source.trim();
+            if source.is_empty() || source.contains(char::is_whitespace) {
+                return Err(GraphError::Syntax {
+                    line: number,
+                    message: format!("{:?} is not a device name", source),
+                });
+            }
+            if let Some(&first) = defined_on.get(source) {
+                return Err(GraphError::DuplicateDefinition {
+                    name: source.to_string(),
+                    first,
+                    again: number,
+                });
+            }
+            defined_on.insert(source, number);
+
+            let from = labeled.add_node(source);
+            let mut seen: Vec<&str> = Vec::new();
+            for target in targets.split_whitespace() {
+                if seen.contains(&target) {
+                    return Err(GraphError::DuplicateTarget {
+                        name: source.to_string(),
+                        target: target.to_string(),
+                        line: number,
+                    });
+                }
+                seen.push(target);
+                first_use.push((target, number));
+                let to = labeled.add_node(target);
+                labeled.graph.add_edge(from, to, ());
+            }
+        }
+
+        for (target, line) in first_use {
+            if !defined_on.contains_key(target) && !self.sinks.iter().any(|s| s == target) {
+                return Err(GraphError::DanglingTarget {
+                    name: target.to_string(),
+                    line,
+                });
+            }
+        }
+        Ok(labeled)
+    }
+}
+
+impl LabeledGraph {
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::default()
+    }
+
+    fn add_node(&mut self, name: &str) -> NodeIndex {
+        if let Some(&node) = self.index.get(name) {
+            return node;
+        }
+        let node = self.graph.add_node(name.to_string());
+        self.index.insert(name.to_string(), node);
+        node
+    }
+
+    pub fn graph(&self) -> &DiGraph<String, ()> {
+        &self.graph
+    }
+
+    pub fn node(&self, name: &str) -> Result<NodeIndex, GraphError> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or_else(|| GraphError::UnknownNode(name.to_string()))
+    }
+
+    pub fn name(&self, node: NodeIndex) -> &str {
+        &self.graph[node]
+    }
+
+    /// Node names joined with arrows, for printing paths and cycles.
+    pub fn path_names(&self, nodes: &[NodeIndex]) -> String {
+        let names: Vec<&str> = nodes.iter().map(|&n| self.name(n)).collect();
+        names.join(" -> ")
+    }
+}
@@ -1,144 +1,196 @@
-use std::{collections::HashMap, fs};
-
-use petgraph::algo::is_cyclic_directed;
-use petgraph::graph::DiGraph;
-use petgraph::prelude::NodeIndex;
-
-fn get_total_paths(
-    graph: &DiGraph<u32, ()>,
-    name_map: &HashMap<String, u32>,
-    start_name: &str,
-    end_name: &str,
-) -> u64 {
-    // 1. Find the start and end NodeIndex values from the names.
-    let start_node = name_map
-        .get(start_name)
-        .expect("not found start")
-        .to_owned();
-    let end_node = name_map.get(end_name).expect("not found end").to_owned();
-
-    // 2. Create a cache for memoization. The key is the node we are starting from,
-    //    and the value is the number of paths from it to the final `end_node`.
-    let mut cache = HashMap::<NodeIndex, u64>::new();
-
-    // 3. Call the internal recursive function to do the actual work.
-    count_paths_recursive(graph, start_node.into(), end_node.into(), &mut cache)
-}
-
-/// Recursive function with memoization to count paths.
-fn count_paths_recursive(
-    graph: &DiGraph<u32, ()>,
-    current_node: NodeIndex,
-    end_node: NodeIndex,
-    cache: &mut HashMap<NodeIndex, u64>,
-) -> u64 {
-    // Base Case 1: If we have reached the end node, we've found one valid path.
-    if current_node == end_node {
-        return 1;
+mod enumerate;
+mod export;
+mod graph;
+mod paths;
+
+use std::{env, fs};
+
+use enumerate::{PathSampler, Paths, SplitMix64, k_shortest};
+use export::Reachability;
+use graph::LabeledGraph;
+use paths::{PathCounter, PathError};
+use petgraph::graph::NodeIndex;
+
+/// Usage: `day11 [FILE] [--from NAME --to NAME [--via NAME]... [--avoid NAME]...]
+/// [--dead] [--export dot|graphml|json] [--output PATH] [--list N] [--shortest K]
+/// [--sample N] [--seed S]`
+///
+/// Part 1 counts paths from `you` to `out`, part 2 from `svr` to `out` through
+/// both `dac` and `fft`. A part is skipped if the file lacks its nodes.
+/// `--from` and `--to` count paths between any two nodes instead, visiting
+/// every `--via` node in any order and no `--avoid` node.
+///
+/// `--dead` lists the devices on no path from the start to the target, and
+/// `--export` writes the graph with the paths between them highlighted. The
+/// start is `--from` if given, otherwise `you` and `svr`, and the target is
+/// `--to` or `out`.
+///
+/// `--list` prints the first N paths from the start to the target in search
+/// order, `--shortest` the K with the fewest hops, and `--sample` N paths
+/// drawn uniformly at random (seeded with `--seed`, default 1). Sampled paths
+/// are checked edge by edge. Here the start is `--from` or `you`.
+fn main() {
+    let mut filename = "puzzle.txt".to_string();
+    let mut query: (Option<String>, Option<String>) = (None, None);
+    let mut via: Vec<String> = Vec::new();
+    let mut avoid: Vec<String> = Vec::new();
+    let mut dead = false;
+    let mut format = None;
+    let mut output = None;
+    let mut list = None;
+    let mut shortest = None;
+    let mut sample = None;
+    let mut seed = 1;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("{} needs a node name", arg))
+        };
+        match arg.as_str() {
+            "--from" => query.0 = Some(value()),
+            "--to" => query.1 = Some(value()),
+            "--via" => via.push(value()),
+            "--avoid" => avoid.push(value()),
+            "--dead" => dead = true,
+            "--export" => format = Some(args.next().expect("--export needs a format")),
+            "--output" => output = Some(args.next().expect("--output needs a path")),
+            "--list" | "--shortest" | "--sample" | "--seed" => {
+                let n: usize = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| panic!("{} needs a number", arg));
+                match arg.as_str() {
+                    "--list" => list = Some(n),
+                    "--shortest" => shortest = Some(n),
+                    "--sample" => sample = Some(n),
+                    _ => seed = n as u64,
+                }
+            }
+            _ => filename = arg,
+        }
     }
-
-    // Base Case 2 (Memoization): If we have already calculated the number of paths
-    // from `current_node`, return the cached result immediately.
-    if let Some(&count) = cache.get(&current_node) {
-        return count;
+    let contents = fs::read_to_string(&filename)
+        .unwrap_or_else(|_| panic!("Failed to read file: {}", filename));
+    let devices = LabeledGraph::builder()
+        .sink("out")
+        .parse(&contents)
+        .unwrap_or_else(|e| panic!("{}: {}", filename, e));
+    let counter = PathCounter::new(devices.graph());
+    let node = |name: &str| devices.node(name).unwrap_or_else(|e| panic!("{}", e));
+
+    if dead || format.is_some() {
+        let starts: Vec<NodeIndex> = match &query.0 {
+            Some(from) => vec![node(from)],
+            None => ["you", "svr"]
+                .iter()
+                .filter_map(|name| devices.node(name).ok())
+                .collect(),
+        };
+        let target = node(query.1.as_deref().unwrap_or("out"));
+        let reach = Reachability::new(&counter, &starts, target);
+        if dead {
+            print!("{}", reach.dead_report(&devices));
+        }
+        if let Some(format) = format {
+            let exported = match format.as_str() {
+                "dot" => reach.to_dot(&devices),
+                "graphml" => reach.to_graphml(&devices),
+                "json" => reach.to_json(&devices),
+                other => panic!("Unknown export format: {}", other),
+            };
+            match output {
+                Some(path) => {
+                    fs::write(&path, exported)
+                        .unwrap_or_else(|_| panic!("Failed to write {}", path));
+                    println!("Graph written to {}", path);
+                }
+                None => print!("{}", exported),
+            }
+        }
+        return;
     }
 
-    // Recursive Step: The total paths from `current_node` is the sum of the paths
-    // from all its neighbors.
-    let mut path_count: u64 = 0;
-    graph
-        .neighbors(current_node)
-        .for_each(|neighbor| path_count += count_paths_recursive(graph, neighbor, end_node, cache));
-
-    // After calculating, store the result in the cache before returning.
-    cache.insert(current_node, path_count);
-
-    path_count
-}
-
-fn count_paths_via_sequence(
-    graph: &DiGraph<u32, ()>,
-    name_map: &HashMap<String, u32>,
-    start_name: &str,
-    via_names: &[&str],
-    end_name: &str,
-) -> u64 {
-    // 1. Create the full chain of nodes to visit in order.
-    let mut path_chain = Vec::with_capacity(via_names.len() + 2);
-    path_chain.push(start_name);
-    path_chain.extend_from_slice(via_names);
-    path_chain.push(end_name);
-
-    // 2. Initialize total paths to 1 for multiplication.
-    let mut total_paths: u64 = 1;
-
-    // 3. Iterate through the chain in overlapping pairs (segments).
-    //    e.g., [start, via1, via2, end] -> [start, via1], [via1, via2], [via2, end]
-    for segment in path_chain.windows(2) {
-        let segment_start = segment[0];
-        let segment_end = segment[1];
-
-        // Calculate paths for this specific segment.
-        let segment_paths = get_total_paths(graph, name_map, segment_start, segment_end);
-
-        // If any segment is impossible, the whole chain is impossible.
-        if segment_paths == 0 {
-            return 0;
+    if list.is_some() || shortest.is_some() || sample.is_some() {
+        let from = query.0.as_deref().unwrap_or("you");
+        let to = query.1.as_deref().unwrap_or("out");
+        let (source, target) = (node(from), node(to));
+        let fail = |e: PathError| -> ! { panic!("{}", describe(&devices, Err(e))) };
+        if let Some(n) = list {
+            let paths: Vec<_> = Paths::new(&counter, source, target)
+                .unwrap_or_else(|e| fail(e))
+                .take(n)
+                .collect();
+            println!("First {} paths from {} to {}:", paths.len(), from, to);
+            for path in &paths {
+                println!("  {}", devices.path_names(path));
+            }
         }
-
-        // Multiply the total by the number of ways this segment can be traversed.
-        total_paths *= segment_paths;
+        if let Some(k) = shortest {
+            let paths = k_shortest(&counter, source, target, k).unwrap_or_else(|e| fail(e));
+            println!("{} shortest paths from {} to {}:", paths.len(), from, to);
+            for path in &paths {
+                println!("  {} hops: {}", path.len() - 1, devices.path_names(path));
+            }
+        }
+        if let Some(n) = sample {
+            let sampler = PathSampler::new(&counter, source, target).unwrap_or_else(|e| fail(e));
+            let mut rng = SplitMix64::new(seed);
+            println!("{} random paths from {} to {}:", n, from, to);
+            for _ in 0..n {
+                let Some(path) = sampler.sample(&mut rng) else {
+                    println!("  there are none");
+                    break;
+                };
+                let valid = path[0] == source
+                    && path[path.len() - 1] == target
+                    && path
+                        .windows(2)
+                        .all(|w| devices.graph().contains_edge(w[0], w[1]));
+                let check = if valid { "" } else { " (NOT A VALID PATH)" };
+                println!("  {}{}", devices.path_names(&path), check);
+            }
+        }
+        return;
     }
 
-    total_paths
-}
+    if let (Some(from), Some(to)) = &query {
+        let required: Vec<NodeIndex> = via.iter().map(|n| node(n)).collect();
+        let forbidden: Vec<NodeIndex> = avoid.iter().map(|n| node(n)).collect();
+        let paths = counter.count_constrained(node(from), node(to), &required, &forbidden);
+        println!(
+            "Paths from {} to {}: {}",
+            from,
+            to,
+            describe(&devices, paths)
+        );
+        return;
+    }
 
-fn main() {
-    let contents = fs::read_to_string("test_2.txt").expect("Failed to read puzzle.txt");
-    let edges: Vec<(String, String)> = contents
-        .lines()
-        .flat_map(|line| {
-            let (source, targets) = line.split_once(":").expect("readme promised");
-            let tv: Vec<String> = targets.split_whitespace().map(|s| s.to_string()).collect();
-            tv.into_iter()
-                .map(|dest| (source.to_owned(), dest))
-                .collect::<Vec<(String, String)>>()
-        })
-        .collect();
-    // Create a mapping from String node names to u32 IDs
-    let mut name_to_id = HashMap::<String, u32>::new();
-    let mut next_id = 0u32;
+    if let (Ok(you), Ok(out)) = (devices.node("you"), devices.node("out")) {
+        let paths = counter.count(you, out);
+        println!("Part 1 Answer: {}", describe(&devices, paths));
+    }
 
-    // First pass: assign IDs to all unique nodes
-    for (source, target) in &edges {
-        name_to_id.entry(source.clone()).or_insert_with(|| {
-            let id = next_id;
-            next_id += 1;
-            id
-        });
-        name_to_id.entry(target.clone()).or_insert_with(|| {
-            let id = next_id;
-            next_id += 1;
-            id
-        });
+    if let (Ok(svr), Ok(dac), Ok(fft), Ok(out)) = (
+        devices.node("svr"),
+        devices.node("dac"),
+        devices.node("fft"),
+        devices.node("out"),
+    ) {
+        let paths = counter.count_constrained(svr, out, &[dac, fft], &[]);
+        println!("Part 2 Answer: {}", describe(&devices, paths));
     }
+}
 
-    // Second pass: create edges vector with mapped u32 values
-    let mapped_edges: Vec<(u32, u32)> = edges
-        .iter()
-        .map(|(source, target)| {
-            (
-                *name_to_id.get(source).unwrap(),
-                *name_to_id.get(target).unwrap(),
-            )
-        })
-        .collect();
-    let gr = DiGraph::<u32, ()>::from_edges(&mapped_edges);
-    println!("{}", is_cyclic_directed(&gr));
-    let pt = get_total_paths(&gr, &name_to_id, "svr", "out");
-    let pt2 = count_paths_via_sequence(&gr, &name_to_id, "svr", &["dac", "fft"], "out")
-        + count_paths_via_sequence(&gr, &name_to_id, "svr", &["fft", "dac"], "out");
-    println!("Name mapping: {:?}", name_to_id);
-    println!("Mapped edges: {:?}", mapped_edges);
-    println!("{}", pt2)
+/// A path count, or why there isn't one, with cycles spelled out by name.
+fn describe(devices: &LabeledGraph, paths: Result<u128, PathError>) -> String {
+    match paths {
+        Ok(paths) => paths.to_string(),
+        Err(PathError::Cycle(cycle)) => format!(
+            "infinitely many, through the cycle {}",
+            devices.path_names(&cycle)
+        ),
+        Err(e) => e.to_string(),
+    }
 }
@@ -0,0 +1,379 @@
+use petgraph::Direction;
+use petgraph::algo::kosaraju_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Most nodes `count_constrained` can require, one bit each.
+const MAX_REQUIRED: usize = 16;
+
+/// Why a path count has no finite answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// A cycle can be entered from the source and left towards the target,
+    /// so there are infinitely many paths. Holds the cycle's nodes in order.
+    Cycle(Vec<NodeIndex>),
+    /// More paths than fit in a u128
+    Overflow,
+    /// More required nodes than the per-subset table can track
+    TooManyRequired(usize),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::Cycle(nodes) => {
+                let ids: Vec<String> = nodes.iter().map(|n| n.index().to_string()).collect();
+                write!(
+                    f,
+                    "infinitely many paths through the cycle {}",
+                    ids.join(" -> ")
+                )
+            }
+            PathError::Overflow => write!(f, "more paths than fit in a u128"),
+            PathError::TooManyRequired(n) => write!(
+                f,
+                "at most {} nodes can be required, got {}",
+                MAX_REQUIRED, n
+            ),
+        }
+    }
+}
+
+/// Counts paths between any two nodes of a directed graph. The strongly
+/// connected components are found once up front to tell which nodes sit on
+/// a cycle. Everything is iterative, so deep graphs don't overflow the stack.
+pub struct PathCounter<'a, N, E> {
+    graph: &'a DiGraph<N, E>,
+    /// Component index of each node, and whether that component has a cycle
+    component: Vec<usize>,
+    cyclic: Vec<bool>,
+}
+
+impl<'a, N, E> PathCounter<'a, N, E> {
+    pub fn new(graph: &'a DiGraph<N, E>) -> Self {
+        let components = kosaraju_scc(graph);
+        let mut component = vec![0; graph.node_count()];
+        let mut cyclic = Vec::with_capacity(components.len());
+        for (c, nodes) in components.iter().enumerate() {
+            for &node in nodes {
+                component[node.index()] = c;
+            }
+            cyclic.push(nodes.len() > 1 || graph.contains_edge(nodes[0], nodes[0]));
+        }
+        PathCounter {
+            graph,
+            component,
+            cyclic,
+        }
+    }
+
+    pub fn graph(&self) -> &'a DiGraph<N, E> {
+        self.graph
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Nodes reachable from `source`, as a mask over node indices.
+    pub fn reachable_from(&self, source: NodeIndex) -> Vec<bool> {
+        self.reach(source, Direction::Outgoing, &[])
+    }
+
+    /// Nodes from which `target` can be reached, as a mask over node indices.
+    pub fn reaching(&self, target: NodeIndex) -> Vec<bool> {
+        self.reach(target, Direction::Incoming, &[])
+    }
+
+    /// Depth-first search along `direction` that never enters `forbidden`.
+    fn reach(&self, start: NodeIndex, direction: Direction, forbidden: &[NodeIndex]) -> Vec<bool> {
+        let mut seen = vec![false; self.graph.node_count()];
+        if forbidden.contains(&start) {
+            return seen;
+        }
+        seen[start.index()] = true;
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for next in self.graph.neighbors_directed(node, direction) {
+                if !seen[next.index()] && !forbidden.contains(&next) {
+                    seen[next.index()] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Nodes on at least one `source` to `target` path that avoids
+    /// `forbidden`, after checking that none of them is on such a cycle.
+    pub fn between(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        forbidden: &[NodeIndex],
+    ) -> Result<Vec<bool>, PathError> {
+        let from = self.reach(source, Direction::Outgoing, forbidden);
+        let to = self.reach(target, Direction::Incoming, forbidden);
+        let between: Vec<bool> = from.iter().zip(&to).map(|(&a, &b)| a && b).collect();
+        for node in self.graph.node_indices() {
+            if between[node.index()] && self.cyclic[self.component[node.index()]] {
+                // The cycle may have needed a forbidden node
+                if let Some(cycle) = self.cycle_through(node, forbidden) {
+                    return Err(PathError::Cycle(cycle));
+                }
+            }
+        }
+        Ok(between)
+    }
+
+    /// Number of distinct paths from `source` to `target`. A node is a path
+    /// of length zero to itself.
+    pub fn count(&self, source: NodeIndex, target: NodeIndex) -> Result<u128, PathError> {
+        self.count_constrained(source, target, &[], &[])
+    }
+
+    /// Number of paths from `source` to `target` that visit every node in
+    /// `required`, in any order, and none in `forbidden`. Naming a required
+    /// node twice is the same as naming it once.
+    ///
+    /// One pass over the nodes, in reverse topological order of the nodes
+    /// on allowed paths, where each node keeps a count
+    /// per subset of `required`: `paths[v][mask]` is the number of paths from
+    /// `v` to `target` whose required nodes are exactly `mask`.
+    pub fn count_constrained(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        required: &[NodeIndex],
+        forbidden: &[NodeIndex],
+    ) -> Result<u128, PathError> {
+        let paths = self.table(source, target, required, forbidden)?;
+        // The last subset is the one with every required node in it
+        Ok(paths[source.index()].last().copied().unwrap_or(0))
+    }
+
+    /// Number of paths from every node to `target`, zero for nodes that
+    /// `source` can't reach. Used as weights when sampling paths.
+    pub fn path_counts(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Result<Vec<u128>, PathError> {
+        let paths = self.table(source, target, &[], &[])?;
+        Ok(paths
+            .iter()
+            .map(|counts| counts.first().copied().unwrap_or(0))
+            .collect())
+    }
+
+    /// The per-node, per-subset counts behind `count_constrained`. Nodes off
+    /// every allowed path get an empty row.
+    fn table(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        required: &[NodeIndex],
+        forbidden: &[NodeIndex],
+    ) -> Result<Vec<Vec<u128>>, PathError> {
+        // Each node gets one bit, however often it was asked for
+        let mut required = required.to_vec();
+        required.sort_unstable();
+        required.dedup();
+        if required.len() > MAX_REQUIRED {
+            return Err(PathError::TooManyRequired(required.len()));
+        }
+        let between = self.between(source, target, forbidden)?;
+        let mut paths: Vec<Vec<u128>> = vec![Vec::new(); self.graph.node_count()];
+        if required.iter().any(|r| !between[r.index()]) {
+            return Ok(paths);
+        }
+        let bit = |node: NodeIndex| {
+            required
+                .iter()
+                .position(|&r| r == node)
+                .map_or(0, |k| 1_usize << k)
+        };
+        let subsets = 1 << required.len();
+        paths[target.index()] = vec![0; subsets];
+        paths[target.index()][bit(target)] = 1;
+        for node in self.sinks_first(&between) {
+            if node == target {
+                continue;
+            }
+            let own = bit(node);
+            let mut totals = vec![0_u128; subsets];
+            for next in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                if !between[next.index()] {
+                    continue;
+                }
+                for (mask, &count) in paths[next.index()].iter().enumerate() {
+                    totals[mask | own] = totals[mask | own]
+                        .checked_add(count)
+                        .ok_or(PathError::Overflow)?;
+                }
+            }
+            paths[node.index()] = totals;
+        }
+        Ok(paths)
+    }
+
+    /// The nodes marked in `between`, each after all of its successors
+    /// among them. Kahn's algorithm over that subgraph alone: `between` has
+    /// no cycles left, even where a forbidden node was all that broke a
+    /// strongly connected component apart.
+    fn sinks_first(&self, between: &[bool]) -> Vec<NodeIndex> {
+        let mut successors_left = vec![0_usize; self.graph.node_count()];
+        let mut ready = Vec::new();
+        for node in self.graph.node_indices() {
+            if !between[node.index()] {
+                continue;
+            }
+            successors_left[node.index()] = self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .filter(|next| between[next.index()])
+                .count();
+            if successors_left[node.index()] == 0 {
+                ready.push(node);
+            }
+        }
+        let mut order = Vec::new();
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for previous in self.graph.neighbors_directed(node, Direction::Incoming) {
+                if between[previous.index()] {
+                    successors_left[previous.index()] -= 1;
+                    if successors_left[previous.index()] == 0 {
+                        ready.push(previous);
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    /// A shortest cycle through `start` that avoids `forbidden`, found by a
+    /// breadth-first search that stays inside its component.
+    fn cycle_through(&self, start: NodeIndex, forbidden: &[NodeIndex]) -> Option<Vec<NodeIndex>> {
+        let component = self.component[start.index()];
+        let mut came_from: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for next in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                if next == start {
+                    let mut cycle = vec![node];
+                    while let Some(&previous) = came_from.get(cycle.last().unwrap()) {
+                        cycle.push(previous);
+                    }
+                    cycle.reverse();
+                    cycle.push(start);
+                    return Some(cycle);
+                }
+                if self.component[next.index()] == component
+                    && !forbidden.contains(&next)
+                    && !came_from.contains_key(&next)
+                {
+                    came_from.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes(indices: &[u32]) -> Vec<NodeIndex> {
+        indices
+            .iter()
+            .map(|&i| NodeIndex::new(i as usize))
+            .collect()
+    }
+
+    #[test]
+    fn forbidden_node_breaking_a_cycle() {
+        // s=0, a=1, b=2, c=3, out=4: s -> a -> b -> out with b -> c -> a
+        // closing a cycle. Without c the only path is s a b out, whatever
+        // order the nodes were added in.
+        for edges in [
+            vec![(0, 1), (1, 2), (2, 3), (2, 4), (3, 1)],
+            vec![(3, 1), (2, 4), (2, 3), (1, 2), (0, 1)],
+        ] {
+            let graph = DiGraph::<(), ()>::from_edges(&edges);
+            let counter = PathCounter::new(&graph);
+            let [s, c, out] = nodes(&[0, 3, 4])[..] else {
+                unreachable!()
+            };
+            assert_eq!(counter.count_constrained(s, out, &[], &[c]), Ok(1));
+            assert_eq!(
+                counter.count(s, out),
+                Err(PathError::Cycle(nodes(&[1, 2, 3, 1])))
+            );
+        }
+    }
+
+    #[test]
+    fn required_and_forbidden() {
+        // Two diamonds in a row: 0 -> {1, 2} -> 3 -> {4, 5} -> 6
+        let graph = DiGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (2, 3),
+            (3, 4),
+            (3, 5),
+            (4, 6),
+            (5, 6),
+        ]);
+        let counter = PathCounter::new(&graph);
+        let n = nodes(&[0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(counter.count(n[0], n[6]), Ok(4));
+        assert_eq!(counter.count_constrained(n[0], n[6], &[n[1]], &[]), Ok(2));
+        assert_eq!(
+            counter.count_constrained(n[0], n[6], &[n[1], n[5]], &[]),
+            Ok(1)
+        );
+        assert_eq!(
+            counter.count_constrained(n[0], n[6], &[n[1]], &[n[1]]),
+            Ok(0)
+        );
+        assert_eq!(counter.count_constrained(n[0], n[6], &[], &[n[3]]), Ok(0));
+        assert_eq!(counter.count(n[6], n[0]), Ok(0));
+    }
+
+    #[test]
+    fn repeated_required_nodes_count_once() {
+        let graph = DiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let counter = PathCounter::new(&graph);
+        let n = nodes(&[0, 1, 2, 3]);
+        assert_eq!(
+            counter.count_constrained(n[0], n[3], &[n[1], n[1]], &[]),
+            Ok(1)
+        );
+        assert_eq!(
+            counter.count_constrained(n[0], n[3], &[n[3], n[1], n[3]], &[]),
+            Ok(1)
+        );
+        let many = vec![n[1]; MAX_REQUIRED + 1];
+        assert_eq!(counter.count_constrained(n[0], n[3], &many, &[]), Ok(1));
+    }
+
+    #[test]
+    fn too_many_required_nodes() {
+        let graph = DiGraph::<(), ()>::from_edges((0..20).map(|i| (i, i + 1)));
+        let counter = PathCounter::new(&graph);
+        let all = nodes(&(0..21).collect::<Vec<_>>());
+        assert_eq!(
+            counter.count_constrained(all[0], all[20], &all[1..18], &[]),
+            Err(PathError::TooManyRequired(17))
+        );
+        assert_eq!(
+            counter.count_constrained(all[0], all[20], &all[1..17], &[]),
+            Ok(1)
+        );
+    }
+}
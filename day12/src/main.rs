@@ -1,20 +1,20 @@
-use std::fs;
-
-#[derive(Debug, Clone)]
-struct Shape {
-    id: usize,
-    max_size: usize, // rows * columns
-    min_size: usize, // number of '#' characters
-    shape: Vec<Vec<char>>,
-}
+mod packing;
+mod shape;
+
+use std::{env, fs};
+
+use packing::Method;
+use shape::Shape;
 
 #[derive(Debug)]
 struct GridObject {
     dimensions: (usize, usize), // (rows, cols) from "RxC"
-    values: Vec<i32>,
+    values: Vec<usize>,
 }
 
-fn parse_input(contents: &str) -> (Vec<Shape>, Vec<GridObject>) {
+/// Shapes and regions, or the first region line that doesn't read as
+/// `RxC: count count ...`.
+fn parse_input(contents: &str) -> Result<(Vec<Shape>, Vec<GridObject>), String> {
     let lines: Vec<&str> = contents.lines().collect();
     let mut shapes = Vec::new();
     let mut grid_objects = Vec::new();
@@ -54,21 +54,7 @@ fn parse_input(contents: &str) -> (Vec<Shape>, Vec<GridObject>) {
             }
 
             if !shape_lines.is_empty() {
-                let rows = shape_lines.len();
-                let cols = shape_lines[0].len();
-                let max_size = rows * cols;
-                let min_size = shape_lines
-                    .iter()
-                    .flat_map(|row| row.iter())
-                    .filter(|&&c| c == '#')
-                    .count();
-
-                shapes.push(Shape {
-                    id,
-                    max_size,
-                    min_size,
-                    shape: shape_lines,
-                });
+                shapes.push(Shape::new(id, shape_lines));
             }
         }
 
@@ -83,40 +69,65 @@ fn parse_input(contents: &str) -> (Vec<Shape>, Vec<GridObject>) {
             continue;
         }
 
-        if let Some((dims, vals)) = line.split_once(':')
-            && let Some((rows_str, cols_str)) = dims.trim().split_once('x')
-            && let (Ok(rows), Ok(cols)) = (rows_str.parse::<usize>(), cols_str.parse::<usize>())
-        {
-            let values: Vec<i32> = vals
-                .split_whitespace()
-                .filter_map(|s| s.parse::<i32>().ok())
-                .collect();
-
-            grid_objects.push(GridObject {
-                dimensions: (rows, cols),
-                values,
-            });
-        }
+        let Some((dims, vals)) = line.split_once(':') else {
+            return Err(format!("line {}: expected `RxC: counts`", i + 1));
+        };
+        let Some((Ok(rows), Ok(cols))) = dims
+            .trim()
+            .split_once('x')
+            .map(|(r, c)| (r.parse::<usize>(), c.parse::<usize>()))
+        else {
+            return Err(format!("line {}: bad region size {:?}", i + 1, dims));
+        };
+        let values = vals
+            .split_whitespace()
+            .map(|s| {
+                s.parse::<usize>()
+                    .map_err(|_| format!("line {}: bad present count {:?}", i + 1, s))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+
+        grid_objects.push(GridObject {
+            dimensions: (rows, cols),
+            values,
+        });
 
         i += 1;
     }
 
-    (shapes, grid_objects)
+    Ok((shapes, grid_objects))
 }
 
+/// Usage: `day12 [FILE]`
+///
+/// Part 1 counts the regions that can hold all of their presents. Each region
+/// is settled by an area bound, a bounding-box layout, or failing both by an
+/// exhaustive search, and the tally says how many went which way.
 fn main() {
-    let contents = fs::read_to_string("puzzle.txt").expect("Failed to read file");
-    let (shapes, grid_objects) = parse_input(&contents);
+    let filename = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "puzzle.txt".to_string());
+    let contents = fs::read_to_string(&filename).expect("Failed to read file");
+    let (shapes, grid_objects) =
+        parse_input(&contents).unwrap_or_else(|e| panic!("{}: {}", filename, e));
 
     println!("Shapes:");
     for shape in &shapes {
         println!(
-            "  Shape {}: max_size={}, min_size={}",
-            shape.id, shape.max_size, shape.min_size
+            "  Shape {}: max_size={}, min_size={}, orientations={}",
+            shape.id,
+            shape.max_size,
+            shape.min_size,
+            shape.orientations().len()
         );
         for row in &shape.shape {
             println!("    {}", row.iter().collect::<String>());
         }
+        for other in shapes.iter().filter(|o| o.id < shape.id) {
+            if shape.congruent(other) {
+                println!("    same as shape {} turned or flipped", other.id);
+            }
+        }
     }
 
     println!("\nGrid Objects:");
@@ -127,30 +138,28 @@ fn main() {
         );
     }
 
-    let mut def_fits = 0;
-    let mut def_not_fit = 0;
-    let mut hard = 0;
-    grid_objects.into_iter().for_each(|grid| {
-        let mut required_area_min = 0;
-        let mut required_area_max = 0;
-        for (idx, num) in grid.values.iter().enumerate() {
-            required_area_max += num * shapes[idx].max_size as i32;
-            required_area_min += num * shapes[idx].min_size as i32;
-        }
-        if required_area_max <= (grid.dimensions.0 * grid.dimensions.1) as i32 {
-            def_fits += 1
-        } else if required_area_min >= (grid.dimensions.0 * grid.dimensions.1) as i32 {
-            def_not_fit += 1
+    let mut fit = 0;
+    let mut tally = [
+        (Method::Area, 0, 0),
+        (Method::Boxes, 0, 0),
+        (Method::Search, 0, 0),
+    ];
+    for grid in &grid_objects {
+        let (rows_n, cols_n) = grid.dimensions;
+        let (fits, method) = packing::fits(&shapes, &grid.values, rows_n, cols_n)
+            .unwrap_or_else(|e| panic!("{}x{}: {}", rows_n, cols_n, e));
+        let entry = tally.iter_mut().find(|(m, _, _)| *m == method).unwrap();
+        if fits {
+            fit += 1;
+            entry.1 += 1;
         } else {
-            hard += 1
+            entry.2 += 1;
         }
-    });
-
-    //TODO:
-    // At some point try to code up the hard bit where we need to rotate things and get the smallest fully filled
-    // rectangle ones.
-    println!(
-        "easy fit: {},easy not fit {}, hard {}",
-        def_fits, def_not_fit, hard
-    )
+    }
+
+    println!();
+    for (method, yes, no) in tally {
+        println!("{:?}: {} fit, {} don't", method, yes, no);
+    }
+    println!("Part 1 Answer: {}", fit);
 }
@@ -0,0 +1,224 @@
+use crate::shape::{Orientation, Shape};
+
+/// How a region's verdict was reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The pieces' cells alone need more room than the region has
+    Area,
+    /// Every piece gets a bounding box of its own, laid out in a grid
+    Boxes,
+    /// Backtracking over every placement
+    Search,
+}
+
+/// An orientation's cells relative to its first cell in row-major order,
+/// the one that gets put on the first open cell of the region. Later rows
+/// can reach left of it, so columns may be negative. `None` for a shape
+/// without cells, which has nothing to put down.
+fn anchored(orientation: &Orientation) -> Option<Vec<(isize, isize)>> {
+    let cells = orientation.cells();
+    let &(r0, c0) = cells.first()?;
+    Some(
+        cells
+            .iter()
+            .map(|&(r, c)| (r as isize - r0 as isize, c as isize - c0 as isize))
+            .collect(),
+    )
+}
+
+/// Decides whether `counts[i]` copies of shape `i` fit in a `rows` x `cols`
+/// region without overlapping. Pieces may be rotated and flipped, and cells
+/// may be left empty.
+///
+/// The two cheap bounds settle most regions; only what falls between them
+/// is searched. Shapes past the end of `counts` aren't needed at all.
+pub fn fits(
+    shapes: &[Shape],
+    counts: &[usize],
+    rows: usize,
+    cols: usize,
+) -> Result<(bool, Method), String> {
+    if let Some(extra) = counts.iter().skip(shapes.len()).position(|&n| n > 0) {
+        return Err(format!(
+            "presents of shape {} wanted but there are only {} shapes",
+            shapes.len() + extra,
+            shapes.len()
+        ));
+    }
+    let mut counts = counts.to_vec();
+    counts.resize(shapes.len(), 0);
+
+    let needed: usize = shapes
+        .iter()
+        .zip(&counts)
+        .map(|(s, &n)| n * s.min_size)
+        .sum();
+    if needed > rows * cols {
+        return Ok((false, Method::Area));
+    }
+
+    let pieces: usize = counts.iter().sum();
+    // Any orientation will do; the canonical one keeps this reproducible
+    let bounds = shapes.iter().map(|s| s.canonical().bounds());
+    let height = bounds.clone().map(|(r, _)| r).max().unwrap_or(0).max(1);
+    let width = bounds.map(|(_, c)| c).max().unwrap_or(0).max(1);
+    if (rows / height) * (cols / width) >= pieces {
+        return Ok((true, Method::Boxes));
+    }
+
+    let mut search = Search {
+        rows,
+        cols,
+        filled: vec![false; rows * cols],
+        shapes: shapes
+            .iter()
+            .map(|s| s.orientations().iter().filter_map(anchored).collect())
+            .collect(),
+        sizes: shapes.iter().map(|s| s.min_size).collect(),
+        left: counts,
+    };
+    let spare = rows * cols - needed;
+    Ok((search.place(0, needed, spare), Method::Search))
+}
+
+/// Fills the region cell by cell in row-major order. The first open cell is
+/// either covered by some piece with its first cell there, or left empty if
+/// there are spare cells to give away.
+struct Search {
+    rows: usize,
+    cols: usize,
+    filled: Vec<bool>,
+    shapes: Vec<Vec<Vec<(isize, isize)>>>,
+    sizes: Vec<usize>,
+    left: Vec<usize>,
+}
+
+impl Search {
+    /// `needed` is the area of the pieces still to place and `spare` how many
+    /// more cells may stay empty.
+    fn place(&mut self, from: usize, needed: usize, spare: usize) -> bool {
+        if needed == 0 {
+            return true;
+        }
+        let Some(cell) = (from..self.filled.len()).find(|&i| !self.filled[i]) else {
+            return false;
+        };
+        let (row, col) = ((cell / self.cols) as isize, (cell % self.cols) as isize);
+
+        for shape in 0..self.shapes.len() {
+            if self.left[shape] == 0 {
+                continue;
+            }
+            for o in 0..self.shapes[shape].len() {
+                let Some(covered) = self.cover(shape, o, row, col) else {
+                    continue;
+                };
+                for &i in &covered {
+                    self.filled[i] = true;
+                }
+                self.left[shape] -= 1;
+                let done = self.place(cell + 1, needed - self.sizes[shape], spare);
+                self.left[shape] += 1;
+                for &i in &covered {
+                    self.filled[i] = false;
+                }
+                if done {
+                    return true;
+                }
+            }
+        }
+
+        if spare == 0 {
+            return false;
+        }
+        self.filled[cell] = true;
+        let done = self.place(cell + 1, needed, spare - 1);
+        self.filled[cell] = false;
+        done
+    }
+
+    /// The grid cells an orientation would cover with its first cell at
+    /// (`row`, `col`), if they are all inside the region and open.
+    fn cover(&self, shape: usize, o: usize, row: isize, col: isize) -> Option<Vec<usize>> {
+        self.shapes[shape][o]
+            .iter()
+            .map(|&(dr, dc)| {
+                let (r, c) = (row + dr, col + dc);
+                if r < 0 || c < 0 || r as usize >= self.rows || c as usize >= self.cols {
+                    return None;
+                }
+                let i = r as usize * self.cols + c as usize;
+                (!self.filled[i]).then_some(i)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Shape> {
+        let contents = std::fs::read_to_string("test.txt").unwrap();
+        crate::parse_input(&contents).unwrap().0
+    }
+
+    #[test]
+    fn puzzle_example_needs_the_search() {
+        let shapes = example();
+        assert_eq!(
+            fits(&shapes, &[0, 0, 0, 0, 2, 0], 4, 4),
+            Ok((true, Method::Search))
+        );
+        assert_eq!(
+            fits(&shapes, &[1, 0, 1, 0, 2, 2], 12, 5),
+            Ok((true, Method::Search))
+        );
+        assert_eq!(
+            fits(&shapes, &[1, 0, 1, 0, 3, 2], 12, 5),
+            Ok((false, Method::Search))
+        );
+    }
+
+    #[test]
+    fn bounds_settle_the_easy_regions() {
+        let shapes = example();
+        assert_eq!(
+            fits(&shapes, &[0, 0, 0, 0, 3, 0], 4, 4),
+            Ok((false, Method::Area))
+        );
+        assert_eq!(
+            fits(&shapes, &[1, 1, 1, 1, 0, 0], 6, 6),
+            Ok((true, Method::Boxes))
+        );
+    }
+
+    #[test]
+    fn short_count_lists_are_padded() {
+        let shapes = example();
+        assert_eq!(
+            fits(&shapes, &[1, 1], 4, 4),
+            fits(&shapes, &[1, 1, 0, 0, 0, 0], 4, 4)
+        );
+        assert_eq!(fits(&shapes, &[], 1, 1), Ok((true, Method::Boxes)));
+        assert!(fits(&shapes, &[0, 0, 0, 0, 0, 0, 0], 4, 4).is_ok());
+        assert!(fits(&shapes, &[0, 0, 0, 0, 0, 0, 1], 4, 4).is_err());
+    }
+
+    #[test]
+    fn empty_shapes_take_no_room() {
+        let shapes = [
+            Shape::new(0, vec![vec!['.']]),
+            Shape::new(1, vec![vec!['#']]),
+        ];
+        assert_eq!(fits(&shapes, &[3, 2], 1, 2), Ok((true, Method::Search)));
+        assert_eq!(fits(&shapes, &[3, 3], 1, 2), Ok((false, Method::Area)));
+    }
+
+    #[test]
+    fn bad_counts_are_reported() {
+        let error = crate::parse_input("0:\n#\n\n2x2: 1 x\n").unwrap_err();
+        assert_eq!(error, "line 4: bad present count \"x\"");
+        assert!(crate::parse_input("0:\n#\n\n2x2: 1 -1\n").is_err());
+    }
+}
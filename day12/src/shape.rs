@@ -0,0 +1,158 @@
+/// One way of laying a shape down: its cells as (row, col) pairs, sorted and
+/// shifted so the topmost row and leftmost column are both 0.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Orientation {
+    cells: Vec<(usize, usize)>,
+}
+
+impl Orientation {
+    fn new(cells: impl IntoIterator<Item = (isize, isize)>) -> Self {
+        let cells: Vec<(isize, isize)> = cells.into_iter().collect();
+        let top = cells.iter().map(|&(r, _)| r).min().unwrap_or(0);
+        let left = cells.iter().map(|&(_, c)| c).min().unwrap_or(0);
+        let mut cells: Vec<(usize, usize)> = cells
+            .iter()
+            .map(|&(r, c)| ((r - top) as usize, (c - left) as usize))
+            .collect();
+        cells.sort_unstable();
+        Orientation { cells }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// Rows and columns of the smallest rectangle around the cells.
+    pub fn bounds(&self) -> (usize, usize) {
+        let rows = self.cells.iter().map(|&(r, _)| r + 1).max().unwrap_or(0);
+        let cols = self.cells.iter().map(|&(_, c)| c + 1).max().unwrap_or(0);
+        (rows, cols)
+    }
+
+    /// Turned a quarter clockwise.
+    fn rotated(&self) -> Self {
+        Orientation::new(self.cells.iter().map(|&(r, c)| (c as isize, -(r as isize))))
+    }
+
+    /// Mirrored left to right.
+    fn flipped(&self) -> Self {
+        Orientation::new(self.cells.iter().map(|&(r, c)| (r as isize, -(c as isize))))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shape {
+    pub id: usize,
+    pub max_size: usize, // rows * columns
+    pub min_size: usize, // number of '#' characters
+    pub shape: Vec<Vec<char>>,
+    /// Every distinct rotation and reflection, smallest first
+    orientations: Vec<Orientation>,
+}
+
+impl Shape {
+    pub fn new(id: usize, shape: Vec<Vec<char>>) -> Self {
+        let rows = shape.len();
+        let cols = shape.first().map_or(0, |row| row.len());
+        let drawn = Orientation::new(shape.iter().enumerate().flat_map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &c)| c == '#')
+                .map(move |(c, _)| (r as isize, c as isize))
+        }));
+
+        let mut orientations = Vec::with_capacity(8);
+        for start in [drawn.clone(), drawn.flipped()] {
+            let mut turned = start;
+            for _ in 0..4 {
+                let next = turned.rotated();
+                orientations.push(turned);
+                turned = next;
+            }
+        }
+        // A symmetric shape lands on the same cells more than once
+        orientations.sort_unstable();
+        orientations.dedup();
+
+        Shape {
+            id,
+            max_size: rows * cols,
+            min_size: drawn.cells.len(),
+            shape,
+            orientations,
+        }
+    }
+
+    pub fn orientations(&self) -> &[Orientation] {
+        &self.orientations
+    }
+
+    /// The smallest orientation, the same for every rotation or reflection
+    /// of a shape.
+    pub fn canonical(&self) -> &Orientation {
+        &self.orientations[0]
+    }
+
+    /// Whether `other` is this shape turned or flipped.
+    pub fn congruent(&self, other: &Shape) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(id: usize, rows: &[&str]) -> Shape {
+        Shape::new(id, rows.iter().map(|row| row.chars().collect()).collect())
+    }
+
+    #[test]
+    fn symmetric_orientations_are_kept_once() {
+        assert_eq!(shape(0, &["##", "##"]).orientations().len(), 1);
+        assert_eq!(shape(0, &["###", "#.#", "###"]).orientations().len(), 1);
+        assert_eq!(shape(0, &["###"]).orientations().len(), 2);
+        assert_eq!(shape(0, &["###", ".#."]).orientations().len(), 4);
+        assert_eq!(shape(0, &["#..", "###"]).orientations().len(), 8);
+    }
+
+    #[test]
+    fn orientations_are_normalised() {
+        let l = shape(0, &["...", ".#.", ".#.", ".##"]);
+        assert_eq!(l.min_size, 4);
+        assert_eq!(l.max_size, 12);
+        for orientation in l.orientations() {
+            let cells = orientation.cells();
+            assert_eq!(cells.len(), 4);
+            assert!(cells.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(cells.iter().map(|&(r, _)| r).min(), Some(0));
+            assert_eq!(cells.iter().map(|&(_, c)| c).min(), Some(0));
+            let (rows, cols) = orientation.bounds();
+            assert!((rows, cols) == (3, 2) || (rows, cols) == (2, 3));
+        }
+    }
+
+    #[test]
+    fn congruence_ignores_turns_and_flips() {
+        let l = shape(0, &["#.", "#.", "##"]);
+        let mirrored = shape(1, &[".#", ".#", "##"]);
+        let turned = shape(2, &["###", "#.."]);
+        let s = shape(3, &[".##", "##."]);
+        let z = shape(4, &["##.", ".##"]);
+        let t = shape(5, &["###", ".#."]);
+        assert!(l.congruent(&mirrored));
+        assert!(l.congruent(&turned));
+        assert!(s.congruent(&z));
+        assert!(!l.congruent(&s));
+        assert!(!l.congruent(&t));
+        assert!(!s.congruent(&t));
+    }
+
+    #[test]
+    fn empty_shape() {
+        let empty = shape(0, &["..", ".."]);
+        assert_eq!(empty.min_size, 0);
+        assert_eq!(empty.orientations().len(), 1);
+        assert_eq!(empty.canonical().bounds(), (0, 0));
+    }
+}
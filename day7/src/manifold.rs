@@ -0,0 +1,510 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Start,
+    /// `^`: splits vertical beams into one on each side, passes horizontal ones.
+    Splitter,
+    /// `/`: a beam going down turns left, going right turns up.
+    MirrorSlash,
+    /// `\`: a beam going down turns right, going right turns down.
+    MirrorBackslash,
+    /// `#`: ends every beam that reaches it.
+    Absorber,
+}
+
+impl TryFrom<char> for Cell {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Cell::Empty),
+            'S' => Ok(Cell::Start),
+            '^' => Ok(Cell::Splitter),
+            '/' => Ok(Cell::MirrorSlash),
+            '\\' => Ok(Cell::MirrorBackslash),
+            '#' => Ok(Cell::Absorber),
+            other => Err(other),
+        }
+    }
+}
+
+impl From<Cell> for char {
+    fn from(cell: Cell) -> char {
+        match cell {
+            Cell::Empty => '.',
+            Cell::Start => 'S',
+            Cell::Splitter => '^',
+            Cell::MirrorSlash => '/',
+            Cell::MirrorBackslash => '\\',
+            Cell::Absorber => '#',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    fn is_vertical(self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    fn reflect(self, cell: Cell) -> Direction {
+        match (cell, self) {
+            (Cell::MirrorSlash, Direction::Down) => Direction::Left,
+            (Cell::MirrorSlash, Direction::Up) => Direction::Right,
+            (Cell::MirrorSlash, Direction::Left) => Direction::Down,
+            (Cell::MirrorSlash, Direction::Right) => Direction::Up,
+            (Cell::MirrorBackslash, Direction::Down) => Direction::Right,
+            (Cell::MirrorBackslash, Direction::Up) => Direction::Left,
+            (Cell::MirrorBackslash, Direction::Left) => Direction::Up,
+            (Cell::MirrorBackslash, Direction::Right) => Direction::Down,
+            _ => self,
+        }
+    }
+}
+
+/// The tachyon manifold: a grid of optical elements with a single `S` where
+/// the beam enters, travelling downwards.
+#[derive(Debug, Clone)]
+pub struct Manifold {
+    cells: Vec<Vec<Cell>>,
+    start: (usize, usize),
+}
+
+/// Which ways beams enter a cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lit {
+    pub vertical: bool,
+    pub horizontal: bool,
+}
+
+/// Result of pushing the beam through the whole manifold.
+#[derive(Debug, Clone)]
+pub struct Propagation {
+    /// Number of splitters hit by at least one beam (part 1). Only beams
+    /// entering vertically are split, horizontal ones pass through.
+    pub splits: u64,
+    /// Which ways beams enter each cell.
+    pub lit: Vec<Vec<Lit>>,
+    /// Timelines passing through each cell. For a splitter this is the number
+    /// of timelines hitting it. Cells fed by a loop, and counts too large for
+    /// a `u64`, hold `u64::MAX`.
+    pub timelines: Vec<Vec<u64>>,
+    pub exited_top: u64,
+    pub exited_bottom: u64,
+    pub exited_left: u64,
+    pub exited_right: u64,
+    /// Timelines that ended in an absorber.
+    pub absorbed: u64,
+    /// Cells of one loop the beam can get caught in, if there is one.
+    pub cycle: Option<Vec<(usize, usize)>>,
+    /// Some timeline count didn't fit in a `u64`.
+    pub overflowed: bool,
+}
+
+/// What happens to a beam after it has been through a cell.
+enum Step {
+    Enter(usize),
+    Exit(Direction),
+    Absorbed,
+}
+
+impl FromStr for Manifold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        let mut start = None;
+        for (row, line) in s.lines().enumerate() {
+            let parsed = line
+                .chars()
+                .enumerate()
+                .map(|(col, c)| {
+                    Cell::try_from(c).map_err(|c| {
+                        format!("unexpected {:?} at row {}, column {}", c, row + 1, col + 1)
+                    })
+                })
+                .collect::<Result<Vec<Cell>, String>>()?;
+            if let Some(first) = cells.first().map(Vec::len)
+                && first != parsed.len()
+            {
+                return Err(format!(
+                    "row {} has {} columns, expected {}",
+                    row + 1,
+                    parsed.len(),
+                    first
+                ));
+            }
+            for (col, cell) in parsed.iter().enumerate() {
+                if *cell == Cell::Start && start.replace((row, col)).is_some() {
+                    return Err(format!("second S at row {}, column {}", row + 1, col + 1));
+                }
+            }
+            cells.push(parsed);
+        }
+        let start = start.ok_or("no S in the manifold")?;
+        Ok(Manifold { cells, start })
+    }
+}
+
+impl Manifold {
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Cell {
+        self.cells[row][col]
+    }
+
+    /// A beam state is a cell together with the direction the beam enters it.
+    fn state(&self, row: usize, col: usize, dir: Direction) -> usize {
+        (row * self.cols() + col) * 4 + dir as usize
+    }
+
+    fn decode(&self, state: usize) -> (usize, usize, Direction) {
+        let cell = state / 4;
+        (
+            cell / self.cols(),
+            cell % self.cols(),
+            Direction::ALL[state % 4],
+        )
+    }
+
+    /// Moves a beam one cell on from `(row, col)`, or off the grid.
+    fn advance(&self, row: usize, col: usize, dir: Direction) -> Step {
+        let next = match dir {
+            Direction::Up => row.checked_sub(1).map(|r| (r, col)),
+            Direction::Down => Some(row + 1).filter(|&r| r < self.rows()).map(|r| (r, col)),
+            Direction::Left => col.checked_sub(1).map(|c| (row, c)),
+            Direction::Right => Some(col + 1).filter(|&c| c < self.cols()).map(|c| (row, c)),
+        };
+        match next {
+            Some((r, c)) => Step::Enter(self.state(r, c, dir)),
+            None => Step::Exit(dir),
+        }
+    }
+
+    /// Where a beam entering `state` goes next.
+    fn steps(&self, state: usize) -> Vec<Step> {
+        let (row, col, dir) = self.decode(state);
+        let cell = self.cells[row][col];
+        match cell {
+            Cell::Absorber => vec![Step::Absorbed],
+            // The split beams appear in the neighbouring cells and keep going
+            // the same way, so they enter those cells rather than leave this one.
+            Cell::Splitter if dir.is_vertical() => {
+                let left = match col.checked_sub(1) {
+                    Some(c) => Step::Enter(self.state(row, c, dir)),
+                    None => Step::Exit(Direction::Left),
+                };
+                let right = if col + 1 < self.cols() {
+                    Step::Enter(self.state(row, col + 1, dir))
+                } else {
+                    Step::Exit(Direction::Right)
+                };
+                vec![left, right]
+            }
+            _ => vec![self.advance(row, col, dir.reflect(cell))],
+        }
+    }
+
+    /// Pushes the beam through the manifold starting at `S` going down.
+    ///
+    /// Beams that end up in the same cell going the same way merge, and the
+    /// number of timelines is counted over the graph of beam states in
+    /// topological order. If that graph has a loop, every state fed by it has
+    /// unboundedly many timelines; those are reported as `u64::MAX` and one
+    /// such loop is returned in `cycle`.
+    pub fn propagate(&self) -> Propagation {
+        let (rows, cols) = (self.rows(), self.cols());
+        let total_states = rows * cols * 4;
+
+        // Collect every reachable state and the edges between them
+        let start = self.state(self.start.0, self.start.1, Direction::Down);
+        let mut reachable = vec![false; total_states];
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); total_states];
+        let mut in_degree = vec![0_usize; total_states];
+        let mut queue = VecDeque::from([start]);
+        reachable[start] = true;
+        while let Some(state) = queue.pop_front() {
+            for step in self.steps(state) {
+                if let Step::Enter(next) = step {
+                    predecessors[next].push(state);
+                    in_degree[next] += 1;
+                    if !reachable[next] {
+                        reachable[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        let mut lit = vec![vec![Lit::default(); cols]; rows];
+        for state in (0..total_states).filter(|&s| reachable[s]) {
+            let (row, col, dir) = self.decode(state);
+            if dir.is_vertical() {
+                lit[row][col].vertical = true;
+            } else {
+                lit[row][col].horizontal = true;
+            }
+        }
+        let splits = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.cells[row][col] == Cell::Splitter && lit[row][col].vertical)
+            .count() as u64;
+
+        // Count paths in topological order (Kahn)
+        let mut counts = vec![0_u64; total_states];
+        let mut done = vec![false; total_states];
+        let mut propagation = Propagation {
+            splits,
+            lit,
+            timelines: vec![vec![0; cols]; rows],
+            exited_top: 0,
+            exited_bottom: 0,
+            exited_left: 0,
+            exited_right: 0,
+            absorbed: 0,
+            cycle: None,
+            overflowed: false,
+        };
+        let mut overflowed = false;
+        counts[start] = 1;
+        let mut ready: VecDeque<usize> = (in_degree[start] == 0)
+            .then_some(start)
+            .into_iter()
+            .collect();
+        while let Some(state) = ready.pop_front() {
+            done[state] = true;
+            let count = counts[state];
+            for step in self.steps(state) {
+                match step {
+                    Step::Enter(next) => {
+                        accumulate(&mut counts[next], count, &mut overflowed);
+                        in_degree[next] -= 1;
+                        if in_degree[next] == 0 {
+                            ready.push_back(next);
+                        }
+                    }
+                    Step::Exit(dir) => {
+                        accumulate(propagation.exit_mut(dir), count, &mut overflowed)
+                    }
+                    Step::Absorbed => accumulate(&mut propagation.absorbed, count, &mut overflowed),
+                }
+            }
+        }
+
+        // Anything reachable but never finished is on a loop or downstream of one
+        let stuck: Vec<usize> = (0..total_states)
+            .filter(|&s| reachable[s] && !done[s])
+            .collect();
+        for &state in &stuck {
+            counts[state] = u64::MAX;
+        }
+        if let Some(&first) = stuck.first() {
+            propagation.cycle = Some(self.find_cycle(first, &predecessors, &done));
+        }
+
+        for (state, &count) in counts.iter().enumerate() {
+            let (row, col, _) = self.decode(state);
+            let cell = &mut propagation.timelines[row][col];
+            *cell = cell.saturating_add(count);
+        }
+        propagation.overflowed = overflowed;
+        propagation
+    }
+
+    /// Walks backwards from a stuck state through stuck predecessors until a
+    /// state repeats. Every stuck state has one, so this always ends on a loop.
+    fn find_cycle(
+        &self,
+        from: usize,
+        predecessors: &[Vec<usize>],
+        done: &[bool],
+    ) -> Vec<(usize, usize)> {
+        let mut seen_at = vec![None; done.len()];
+        let mut path = Vec::new();
+        let mut state = from;
+        while seen_at[state].is_none() {
+            seen_at[state] = Some(path.len());
+            path.push(state);
+            state = *predecessors[state]
+                .iter()
+                .find(|&&p| !done[p])
+                .expect("stuck states have a stuck predecessor");
+        }
+        let loop_start = seen_at[state].unwrap();
+        path[loop_start..]
+            .iter()
+            .rev()
+            .map(|&s| {
+                let (row, col, _) = self.decode(s);
+                (row, col)
+            })
+            .collect()
+    }
+}
+
+impl Propagation {
+    fn exit_mut(&mut self, dir: Direction) -> &mut u64 {
+        match dir {
+            Direction::Up => &mut self.exited_top,
+            Direction::Down => &mut self.exited_bottom,
+            Direction::Left => &mut self.exited_left,
+            Direction::Right => &mut self.exited_right,
+        }
+    }
+
+    /// Total number of timelines at the end (part 2): everything that left the
+    /// grid on any side or was absorbed. `None` if the beam can loop forever
+    /// or there are too many to count in a `u64`.
+    pub fn total_timelines(&self) -> Option<u64> {
+        if self.cycle.is_some() || self.overflowed {
+            return None;
+        }
+        [
+            self.exited_bottom,
+            self.exited_left,
+            self.exited_right,
+            self.absorbed,
+        ]
+        .into_iter()
+        .try_fold(self.exited_top, u64::checked_add)
+    }
+}
+
+/// Adds `count` to `total`, holding it at `u64::MAX` and setting `overflowed`
+/// if it doesn't fit.
+fn accumulate(total: &mut u64, count: u64, overflowed: &mut bool) {
+    match total.checked_add(count) {
+        Some(sum) => *total = sum,
+        None => {
+            *total = u64::MAX;
+            *overflowed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn propagate(rows: &[&str]) -> Propagation {
+        rows.join("\n").parse::<Manifold>().unwrap().propagate()
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let contents = std::fs::read_to_string("test.txt").unwrap();
+        let propagation = contents.parse::<Manifold>().unwrap().propagate();
+        assert_eq!(propagation.splits, 21);
+        assert_eq!(propagation.total_timelines(), Some(40));
+        assert_eq!(propagation.exited_bottom, 40);
+    }
+
+    #[test]
+    fn mirrors_reflect() {
+        let right = propagate(&[".S.", ".\\.", "..."]);
+        assert_eq!(right.exited_right, 1);
+        assert_eq!(right.timelines[1][2], 1);
+        assert_eq!(right.timelines[2][1], 0);
+        let left = propagate(&[".S.", "./.", "..."]);
+        assert_eq!(left.exited_left, 1);
+        assert_eq!(left.total_timelines(), Some(1));
+        // Down, right, then back down and out the bottom
+        let back_down = propagate(&["S..", "\\.\\", "..."]);
+        assert_eq!(back_down.exited_bottom, 1);
+        assert_eq!(back_down.timelines[2][2], 1);
+    }
+
+    #[test]
+    fn absorbers_end_beams() {
+        let propagation = propagate(&[".S.", ".^.", "#.."]);
+        assert_eq!(propagation.absorbed, 1);
+        assert_eq!(propagation.exited_bottom, 1);
+        assert_eq!(propagation.total_timelines(), Some(2));
+    }
+
+    #[test]
+    fn splitters_on_the_edge_send_beams_out_the_side() {
+        let propagation = propagate(&["S.", "^."]);
+        assert_eq!(propagation.splits, 1);
+        assert_eq!(propagation.exited_left, 1);
+        assert_eq!(propagation.exited_bottom, 1);
+        let propagation = propagate(&[".S", ".^"]);
+        assert_eq!(propagation.exited_right, 1);
+        assert_eq!(propagation.exited_bottom, 1);
+    }
+
+    #[test]
+    fn horizontal_beams_pass_splitters() {
+        let propagation = propagate(&[".S..", ".\\^."]);
+        assert_eq!(propagation.splits, 0);
+        assert_eq!(propagation.exited_right, 1);
+        assert_eq!(
+            propagation.lit[1][2],
+            Lit {
+                vertical: false,
+                horizontal: true
+            }
+        );
+    }
+
+    #[test]
+    fn loops_are_found() {
+        let propagation = propagate(&["..S.", "./.\\", "..^.", ".\\./"]);
+        assert_eq!(propagation.total_timelines(), None);
+        let ring = [
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (3, 2),
+            (3, 1),
+            (2, 1),
+        ];
+        let cycle = propagation.cycle.unwrap();
+        assert_eq!(cycle.len(), ring.len());
+        assert!(cycle.iter().all(|cell| ring.contains(cell)));
+        assert_eq!(propagation.timelines[2][3], u64::MAX);
+        assert!(!propagation.overflowed);
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let mut rows = vec![format!("{}S{}", ".".repeat(20), ".".repeat(20))];
+        for row in 0..120 {
+            rows.push(
+                (0..41)
+                    .map(|col| if (row + col) % 2 == 0 { '^' } else { '.' })
+                    .collect(),
+            );
+        }
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let propagation = propagate(&rows);
+        assert!(propagation.overflowed);
+        assert!(propagation.cycle.is_none());
+        assert_eq!(propagation.total_timelines(), None);
+    }
+}
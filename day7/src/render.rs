@@ -0,0 +1,142 @@
+use crate::manifold::{Cell, Manifold, Propagation};
+use std::{fs, io, path::Path};
+
+/// Splitters that at least one beam ran into.
+const HIT_SPLITTER: char = '*';
+/// Glyphs for the heatmap, one per decade of timeline counts.
+const DECADES: &[u8] = b"0123456789abcdefghij";
+
+/// Draws the manifold back as text with every cell a beam passes through
+/// marked `|`, `-` or `+` for vertical, horizontal or both. Splitters a beam
+/// ran into vertically are drawn as `*`, the others keep their `^`, mirrors
+/// and absorbers are drawn as they were read.
+pub fn beams(manifold: &Manifold, propagation: &Propagation) -> String {
+    render_with(manifold, |row, col| {
+        let lit = propagation.lit[row][col];
+        match (manifold.cell(row, col), lit.vertical, lit.horizontal) {
+            (Cell::Splitter, true, _) => HIT_SPLITTER,
+            (Cell::Empty, true, true) => '+',
+            (Cell::Empty, true, false) => '|',
+            (Cell::Empty, false, true) => '-',
+            (cell, _, _) => cell.into(),
+        }
+    })
+}
+
+/// Draws the number of timelines through each cell on a log10 scale, one
+/// glyph per decade, followed by a legend.
+pub fn heatmap(manifold: &Manifold, propagation: &Propagation) -> String {
+    let mut out = render_with(manifold, |row, col| {
+        let count = propagation.timelines[row][col];
+        match (manifold.cell(row, col), count) {
+            (Cell::Start, _) => 'S',
+            (cell, 0) => cell.into(),
+            _ => DECADES[decade(count)] as char,
+        }
+    });
+
+    let max_decade = propagation
+        .timelines
+        .iter()
+        .flatten()
+        .filter(|&&count| count > 0)
+        .map(|&count| decade(count))
+        .max()
+        .unwrap_or(0);
+    out.push_str("\nLegend (timelines per cell):\n");
+    for (d, &glyph) in DECADES.iter().enumerate().take(max_decade + 1) {
+        let low = 10_u64.pow(d as u32);
+        match 10_u64.checked_pow(d as u32 + 1) {
+            Some(high) => out.push_str(&format!("  {}: {}..={}\n", glyph as char, low, high - 1)),
+            None => out.push_str(&format!("  {}: {}..\n", glyph as char, low)),
+        }
+    }
+    out
+}
+
+/// Writes the heatmap as a binary PPM image with `scale`×`scale` pixels per
+/// cell. Empty cells are black, splitters grey and beams go from blue (one
+/// timeline) to red (the busiest cell).
+pub fn write_ppm(
+    path: impl AsRef<Path>,
+    manifold: &Manifold,
+    propagation: &Propagation,
+    scale: usize,
+) -> io::Result<()> {
+    let (rows, cols) = (manifold.rows(), manifold.cols());
+    let max_log = propagation
+        .timelines
+        .iter()
+        .flatten()
+        .map(|&count| (count as f64).ln_1p())
+        .fold(0.0, f64::max);
+
+    let mut data = format!("P6\n{} {}\n255\n", cols * scale, rows * scale).into_bytes();
+    for row in 0..rows {
+        let pixels: Vec<[u8; 3]> = (0..cols)
+            .map(|col| {
+                let count = propagation.timelines[row][col];
+                match manifold.cell(row, col) {
+                    Cell::Start => [255, 255, 255],
+                    Cell::Splitter if count == 0 => [96, 96, 96],
+                    _ if count == 0 => [0, 0, 0],
+                    _ => {
+                        let t = if max_log > 0.0 {
+                            (count as f64).ln_1p() / max_log
+                        } else {
+                            1.0
+                        };
+                        [(255.0 * t) as u8, 0, (255.0 * (1.0 - t)) as u8]
+                    }
+                }
+            })
+            .collect();
+        for _ in 0..scale {
+            for pixel in &pixels {
+                for _ in 0..scale {
+                    data.extend_from_slice(pixel);
+                }
+            }
+        }
+    }
+    fs::write(path, data)
+}
+
+fn decade(count: u64) -> usize {
+    count.ilog10() as usize
+}
+
+fn render_with(manifold: &Manifold, glyph: impl Fn(usize, usize) -> char) -> String {
+    let mut out = String::with_capacity(manifold.rows() * (manifold.cols() + 1));
+    for row in 0..manifold.rows() {
+        out.extend((0..manifold.cols()).map(|col| glyph(row, col)));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beams_of(rows: &[&str]) -> String {
+        let manifold: Manifold = rows.join("\n").parse().unwrap();
+        beams(&manifold, &manifold.propagate())
+    }
+
+    #[test]
+    fn beams_show_their_direction() {
+        assert_eq!(beams_of(&[".S.", ".^.", "..."]), ".S.\n|*|\n|.|\n");
+        assert_eq!(beams_of(&["S...", "\\..."]), "S...\n\\---\n");
+        // The right half of the split turns back across the left half
+        assert_eq!(
+            beams_of(&["..S.", "..^.", ".../", "...."]),
+            "..S.\n.|*|\n-+-/\n.|..\n"
+        );
+    }
+
+    #[test]
+    fn horizontal_beams_pass_splitters_untouched() {
+        assert_eq!(beams_of(&[".S..", ".\\^."]), ".S..\n.\\^-\n");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{Connection, Point};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Neighbours fetched per point the first time; doubled on every refill.
+const INITIAL_BATCH: usize = 4;
+
+fn coord(p: &Point, axis: usize) -> i64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+/// Static 3D k-d tree over point indices. The tree is implicit: each range of
+/// `order` stores its splitting point in the middle, smaller coordinates on
+/// the left and larger on the right, cycling through x, y, z with depth.
+/// `nodes` holds the same points in that order, so a search reads memory
+/// roughly front to back instead of jumping around `points`.
+pub struct KdTree<'a> {
+    points: &'a [Point],
+    order: Vec<usize>,
+    nodes: Vec<Point>,
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new(points: &'a [Point]) -> Self {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        build(points, &mut order, 0);
+        let nodes = order.iter().map(|&j| points[j]).collect();
+        KdTree {
+            points,
+            order,
+            nodes,
+        }
+    }
+
+    /// The `k` nearest points other than point `i` itself, sorted by
+    /// (squared distance, index).
+    pub fn nearest(&self, i: usize, k: usize) -> Vec<(i128, usize)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(i, k, 0, self.order.len(), 0, &mut best);
+        }
+        best.into_sorted_vec()
+    }
+
+    fn search(
+        &self,
+        i: usize,
+        k: usize,
+        lo: usize,
+        hi: usize,
+        axis: usize,
+        best: &mut BinaryHeap<(i128, usize)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let (j, node) = (self.order[mid], &self.nodes[mid]);
+        let query = &self.points[i];
+        if j != i {
+            best.push((query.squared_distance_from(node), j));
+            if best.len() > k {
+                best.pop();
+            }
+        }
+
+        let offset = coord(query, axis) as i128 - coord(node, axis) as i128;
+        let (near, far) = if offset < 0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        let next_axis = (axis + 1) % 3;
+        self.search(i, k, near.0, near.1, next_axis, best);
+        // Equal distances still matter on the far side, a smaller index wins the tie
+        let worst = best.peek().map(|&(d, _)| d);
+        if best.len() < k || worst.is_some_and(|d| offset * offset <= d) {
+            self.search(i, k, far.0, far.1, next_axis, best);
+        }
+    }
+}
+
+fn build(points: &[Point], order: &mut [usize], axis: usize) {
+    if order.len() <= 1 {
+        return;
+    }
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by_key(mid, |&p| coord(&points[p], axis));
+    let (left, right) = order.split_at_mut(mid);
+    build(points, left, (axis + 1) % 3);
+    build(points, &mut right[1..], (axis + 1) % 3);
+}
+
+/// Neighbours of one point fetched so far, consumed front to back.
+struct Candidates {
+    found: Vec<(i128, usize)>,
+    next: usize,
+    batch: usize,
+    /// The last query returned fewer than `batch`, so there are no more.
+    complete: bool,
+}
+
+/// Every pair of points exactly once, in increasing (distance, i, j) order.
+///
+/// Each point keeps a short sorted list of its nearest neighbours and a heap
+/// merges the heads of those lists. A pair turns up in the lists of both its
+/// points and is only handed out from the lower-indexed one. A list is only
+/// refilled (with twice as many neighbours) once it runs dry, so taking the
+/// first m pairs costs roughly O((n + m) log n) instead of sorting all n²/2.
+pub struct ClosestPairs<'a> {
+    tree: KdTree<'a>,
+    candidates: Vec<Candidates>,
+    heads: BinaryHeap<Reverse<Connection>>,
+}
+
+impl<'a> ClosestPairs<'a> {
+    pub fn new(points: &'a [Point]) -> Self {
+        let tree = KdTree::new(points);
+        let mut candidates = Vec::with_capacity(points.len());
+        let mut heads = BinaryHeap::with_capacity(points.len());
+        for i in 0..points.len() {
+            let found = tree.nearest(i, INITIAL_BATCH);
+            if let Some(&(d, j)) = found.first() {
+                heads.push(Reverse((d, i, j)));
+            }
+            candidates.push(Candidates {
+                complete: found.len() < INITIAL_BATCH,
+                found,
+                next: 0,
+                batch: INITIAL_BATCH,
+            });
+        }
+        ClosestPairs {
+            tree,
+            candidates,
+            heads,
+        }
+    }
+
+    /// Moves point `i` on to its next neighbour, fetching more if needed.
+    fn advance(&mut self, i: usize) -> Option<(i128, usize)> {
+        let list = &mut self.candidates[i];
+        list.next += 1;
+        if list.next == list.found.len() {
+            if list.complete {
+                list.found = Vec::new();
+                return None;
+            }
+            // Everything up to the old batch size has been handed out already
+            let consumed = list.batch;
+            list.batch *= 2;
+            list.found = self.tree.nearest(i, list.batch);
+            list.complete = list.found.len() < list.batch;
+            list.found.drain(..consumed.min(list.found.len()));
+            list.next = 0;
+        }
+        list.found.get(list.next).copied()
+    }
+}
+
+impl Iterator for ClosestPairs<'_> {
+    type Item = Connection;
+
+    fn next(&mut self) -> Option<Connection> {
+        loop {
+            let Reverse((d, i, j)) = self.heads.pop()?;
+            if let Some((next_d, next_j)) = self.advance(i) {
+                self.heads.push(Reverse((next_d, i, next_j)));
+            }
+            // Each list is sorted by (d, j), so the heap pops in (d, i, j) order
+            if i < j {
+                return Some((d, i, j));
+            }
+        }
+    }
+}
@@ -1,97 +1,196 @@
-use core::f64;
-use ndarray::Array2;
-use std::collections::{HashMap, HashSet, VecDeque};
+mod dsu;
+mod kdtree;
+mod mst;
+
+use dsu::DisjointSet;
+use kdtree::ClosestPairs;
 use std::convert::TryFrom;
-use std::fs;
+use std::{env, fs};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Point {
-    x: f64,
-    y: f64,
-    z: f64,
+    x: i64,
+    y: i64,
+    z: i64,
 }
 
-impl TryFrom<&[f64]> for Point {
+/// A candidate connection: squared distance and the two point indices, `i < j`.
+///
+/// Connections are ordered by the tuple itself, so equal distances are broken
+/// by `i` and then `j`, and the order never depends on the sort algorithm.
+type Connection = (i128, usize, usize);
+
+/// Largest coordinate magnitude accepted. Differences then stay within 2^62,
+/// and three squares of those, 3 * 2^124, fit comfortably in an i128.
+const MAX_COORDINATE: i64 = 1 << 61;
+
+impl TryFrom<&[i64]> for Point {
     type Error = &'static str;
 
-    fn try_from(slice: &[f64]) -> Result<Self, Self::Error> {
-        if slice.len() == 3 {
+    fn try_from(slice: &[i64]) -> Result<Self, Self::Error> {
+        if slice.len() != 3 {
+            Err("Slice must contain exactly 3 elements")
+        } else if slice
+            .iter()
+            .any(|c| c.unsigned_abs() > MAX_COORDINATE as u64)
+        {
+            Err("Coordinates must be within ±2^61")
+        } else {
             Ok(Point {
                 x: slice[0],
                 y: slice[1],
                 z: slice[2],
             })
-        } else {
-            Err("Slice must contain exactly 3 elements")
         }
     }
 }
 
 impl Point {
-    fn distance_from(&self, p: &Point) -> f64 {
-        let sq_dist = (self.x - p.x).powf(2.) + (self.y - p.y).powf(2.) + (self.z - p.z).powf(2.);
-        sq_dist.sqrt()
+    /// Exact squared distance. Comparing these instead of the rooted f64
+    /// distance keeps close pairs apart however large the coordinates get,
+    /// as long as they are within `MAX_COORDINATE` in magnitude.
+    fn squared_distance_from(&self, p: &Point) -> i128 {
+        let dx = self.x as i128 - p.x as i128;
+        let dy = self.y as i128 - p.y as i128;
+        let dz = self.z as i128 - p.z as i128;
+        dx * dx + dy * dy + dz * dz
     }
 }
 
-fn create_distance_matrix(points: &[Point]) -> Array2<f64> {
-    let mut distances = Array2::from_elem((points.len(), points.len()), f64::MAX);
-    // Iterate and populate the array
-    for i in 0..points.len() - 1 {
-        for j in i + 1..points.len() {
-            let dist = points[i].distance_from(&points[j]);
-            // Use tuple indexing (i, j)
-            distances[[i, j]] = dist;
-        }
+/// Sizes of the circuits (largest first) after joining the `n` closest pairs,
+/// whether or not a pair was already in the same circuit.
+fn circuit_sizes_after(
+    n: usize,
+    total_nodes: usize,
+    connections: impl IntoIterator<Item = Connection>,
+) -> Vec<usize> {
+    let mut circuits = DisjointSet::new(total_nodes);
+    for (_, i, j) in connections.into_iter().take(n) {
+        circuits.union(i, j);
     }
-    distances
+    circuits.component_sizes()
 }
 
-fn shortest_connections(n: usize, distances: Array2<f64>) -> Vec<(f64, usize, usize)> {
-    let mut valid_pairs: Vec<(f64, usize, usize)> = distances
-        .indexed_iter()
-        // Keep only the pairs that are not f64::MAX
-        .filter(|(_, dist)| dist < &&f64::MAX)
-        // Remap to (distance, i, j) for easy sorting
-        .map(|((i, j), &dist)| (dist, i, j))
-        .collect();
-
-    // Sort the collected pairs by distance (the first element of the tuple)
-    valid_pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+/// Kruskal over the sorted pairs: the edge that merges the last two circuits.
+fn closing_connection(
+    total_nodes: usize,
+    connections: impl IntoIterator<Item = Connection>,
+) -> Option<(usize, Connection)> {
+    let mut circuits = DisjointSet::new(total_nodes);
+    for (added, (dist, i, j)) in connections.into_iter().enumerate() {
+        if circuits.union(i, j) && circuits.components() == 1 {
+            return Some((added + 1, (dist, i, j)));
+        }
+    }
+    None
+}
 
-    // Take the first N pairs from the sorted list
-    valid_pairs[..n].to_vec()
+fn part1(points: &[Point], budget: usize) {
+    let sizes = circuit_sizes_after(budget, points.len(), ClosestPairs::new(points));
+    println!("Circuit sizes after {} connections: {:?}", budget, sizes);
+    println!("Part 1 Answer: {}", sizes.iter().take(3).product::<usize>());
 }
-fn count_components(adj_list: &HashMap<usize, Vec<usize>>, total_nodes: usize) -> usize {
-    let mut visited: HashSet<usize> = HashSet::new();
-    let mut num_components = 0;
-
-    for node in 0..total_nodes {
-        if !visited.contains(&node) {
-            // Start BFS from this node
-            num_components += 1;
-            let mut queue = VecDeque::new();
-            queue.push_back(node);
-            visited.insert(node);
-
-            while let Some(current) = queue.pop_front() {
-                if let Some(neighbors) = adj_list.get(&current) {
-                    for &neighbor in neighbors {
-                        if !visited.contains(&neighbor) {
-                            visited.insert(neighbor);
-                            queue.push_back(neighbor);
-                        }
-                    }
-                }
-            }
+
+fn part2(points: &[Point]) {
+    match closing_connection(points.len(), ClosestPairs::new(points)) {
+        Some((connections_added, (dist, i, j))) => {
+            println!(
+                "Graph fully connected after {} connections",
+                connections_added
+            );
+            println!(
+                "Last connection added: distance {:.2} between nodes {} and {}",
+                (dist as f64).sqrt(),
+                i,
+                j
+            );
+            println!("These nodes are {:?}, {:?}", points[i], points[j]);
+            // Both within 2^61, so the product can't overflow an i128
+            println!(
+                "Part 2 Answer: {}",
+                points[i].x as i128 * points[j].x as i128
+            );
         }
+        None => println!("Graph never becomes fully connected"),
     }
+}
 
-    num_components
+fn export_forest(points: &[Point], limit: Option<usize>, format: &str, output: Option<String>) {
+    let forest = mst::spanning_forest(points.len(), ClosestPairs::new(points), limit);
+
+    eprintln!("step,considered,components,total_weight,longest_edge");
+    for (step, stats) in forest.steps.iter().enumerate() {
+        eprintln!(
+            "{},{},{},{:.3},{:.3}",
+            step + 1,
+            stats.considered,
+            stats.components,
+            stats.total_weight,
+            stats.longest_edge
+        );
+    }
+    if let Some(last) = forest.steps.last() {
+        eprintln!(
+            "{} edges, {} components, total weight {:.3}, longest edge {:.3}",
+            forest.edges.len(),
+            last.components,
+            last.total_weight,
+            last.longest_edge
+        );
+    }
+
+    let exported = match format {
+        "edges" => forest.to_edge_list(),
+        "dot" => forest.to_dot(points),
+        "json" => forest.to_json(points),
+        other => panic!("Unknown export format: {}", other),
+    };
+    match output {
+        Some(path) => {
+            fs::write(&path, exported).unwrap_or_else(|_| panic!("Failed to write {}", path));
+            println!("Forest written to {}", path);
+        }
+        None => print!("{}", exported),
+    }
 }
 
+/// Usage: `day8 [part1|part2|mst] [FILE] [--connections N] [--format edges|dot|json] [--output PATH]`
+///
+/// Runs both parts when no mode is given. The part 1 connection budget
+/// defaults to 10 for `test.txt` and 1000 for anything else. `mst` exports
+/// the spanning forest after `--connections` pairs, or the full minimum
+/// spanning tree without it. Its statistics per accepted edge go to stderr,
+/// so stdout holds nothing but the exported forest.
 fn main() {
-    let contents = fs::read_to_string("puzzle.txt").expect("Failed to read puzzle.txt");
+    let mut filename = "puzzle.txt".to_string();
+    let mut mode = None;
+    let mut budget = None;
+    let mut format = "edges".to_string();
+    let mut output = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "part1" | "part2" | "mst" => mode = Some(arg),
+            "--connections" => {
+                budget = Some(
+                    args.next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .expect("--connections needs a number"),
+                )
+            }
+            "--format" => format = args.next().expect("--format needs a value"),
+            "--output" => output = Some(args.next().expect("--output needs a path")),
+            _ => filename = arg,
+        }
+    }
+    let part1_budget = budget.unwrap_or(if filename.ends_with("test.txt") {
+        10
+    } else {
+        1000
+    });
+
+    let contents = fs::read_to_string(&filename)
+        .unwrap_or_else(|_| panic!("Failed to read file: {}", filename));
     let parsed: Vec<Point> = contents
         .lines()
         .map(|s| {
@@ -100,67 +199,185 @@ fn main() {
                     .splitn(3, ",")
                     .collect::<Vec<&str>>()
                     .into_iter()
-                    .map(|num| num.parse::<f64>().expect("Puzzle promised numbers"))
-                    .collect::<Vec<f64>>()
+                    .map(|num| num.parse::<i64>().expect("Puzzle promised numbers"))
+                    .collect::<Vec<i64>>()
                     .as_slice(),
             )
-            .expect("Each row has 3 elements")
+            .unwrap_or_else(|e| panic!("Bad point {:?}: {}", s, e))
         })
         .collect();
 
-    let total_nodes = parsed.len();
-    let distances = create_distance_matrix(&parsed);
+    match mode.as_deref() {
+        Some("part1") => part1(&parsed, part1_budget),
+        Some("part2") => part2(&parsed),
+        Some(_) => export_forest(&parsed, budget, &format, output),
+        None => {
+            part1(&parsed, part1_budget);
+            part2(&parsed);
+        }
+    }
+}
 
-    // Get ALL valid connections sorted by distance
-    let mut all_connections: Vec<(f64, usize, usize)> = distances
-        .indexed_iter()
-        .filter(|(_, dist)| dist < &&f64::MAX)
-        .map(|((i, j), &dist)| (dist, i, j))
-        .collect();
-    all_connections.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Start with empty graph
-    let mut adj_list: HashMap<usize, Vec<usize>> = HashMap::new();
+    fn connections(points: &[Point]) -> Vec<Connection> {
+        ClosestPairs::new(points).collect()
+    }
 
-    // Initialize all nodes in the adjacency list (even isolated ones)
-    for i in 0..total_nodes {
-        adj_list.entry(i).or_default();
+    /// All pairs sorted the slow way, to check the k-d tree against.
+    fn brute_force(points: &[Point]) -> Vec<Connection> {
+        let mut pairs = Vec::new();
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                pairs.push((points[i].squared_distance_from(&points[j]), i, j));
+            }
+        }
+        pairs.sort_unstable();
+        pairs
     }
 
-    // Add connections one by one until fully connected
-    let mut connections_added = 0;
-    for &(dist, i, j) in &all_connections {
-        // Add the connection
-        adj_list.entry(i).or_default().push(j);
-        adj_list.entry(j).or_default().push(i);
-        connections_added += 1;
+    #[test]
+    fn exact_order_beyond_f64_precision() {
+        // 2^60 and 2^60 + 1 round to the same f64, so a float sort sees a tie
+        let n = 1_i64 << 30;
+        let points = [
+            Point { x: 0, y: 0, z: 0 },
+            Point { x: 0, y: n, z: 1 },
+            Point { x: n, y: 0, z: 0 },
+        ];
+        let d_far = points[0].squared_distance_from(&points[1]);
+        let d_near = points[0].squared_distance_from(&points[2]);
+        assert_eq!((d_near as f64).sqrt(), (d_far as f64).sqrt());
 
-        // Check if graph is fully connected
-        let num_components = count_components(&adj_list, total_nodes);
+        let edges = connections(&points);
+        assert_eq!(edges[0], (d_near, 0, 2));
+        assert_eq!(edges[1], (d_far, 0, 1));
+    }
 
-        if num_components == 1 {
-            println!(
-                "Graph fully connected after {} connections",
-                connections_added
-            );
-            println!(
-                "Last connection added: distance {:.2} between nodes {} and {}",
-                dist, i, j
-            );
-            println!("These nodes are {:?}, {:?}", parsed[i], parsed[j]);
-            println!("final product is {}", parsed[i].x * parsed[j].x);
-            break;
-        }
+    #[test]
+    fn large_coordinates_do_not_overflow() {
+        let big = 1_i64 << 61;
+        let a = Point {
+            x: -big,
+            y: -big,
+            z: -big,
+        };
+        let b = Point {
+            x: big,
+            y: big,
+            z: big,
+        };
+        assert_eq!(a.squared_distance_from(&b), 3 * (1_i128 << 124));
+    }
 
-        if connections_added % 100 == 0 {
-            println!(
-                "Added {} connections, {} components remaining",
-                connections_added, num_components
-            );
-        }
+    #[test]
+    fn coordinates_out_of_range_are_rejected() {
+        assert!(Point::try_from([MAX_COORDINATE, -MAX_COORDINATE, 0].as_slice()).is_ok());
+        assert!(Point::try_from([0, MAX_COORDINATE + 1, 0].as_slice()).is_err());
+        assert!(Point::try_from([0, 0, i64::MIN].as_slice()).is_err());
+    }
+
+    #[test]
+    fn ties_break_on_indices() {
+        // Unit cube corners: lots of equal distances
+        let points: Vec<Point> = (0..8)
+            .map(|k| Point {
+                x: (k & 1) * 1_000_000_007,
+                y: (k >> 1 & 1) * 1_000_000_007,
+                z: (k >> 2 & 1) * 1_000_000_007,
+            })
+            .collect();
+        let edges = connections(&points);
+        let cube_edges: Vec<(usize, usize)> = edges[..12].iter().map(|&(_, i, j)| (i, j)).collect();
+        assert_eq!(
+            cube_edges,
+            [
+                (0, 1),
+                (0, 2),
+                (0, 4),
+                (1, 3),
+                (1, 5),
+                (2, 3),
+                (2, 6),
+                (3, 7),
+                (4, 5),
+                (4, 6),
+                (5, 7),
+                (6, 7)
+            ]
+        );
+        assert_eq!(edges, connections(&points));
     }
 
-    // Final verification
-    let final_components = count_components(&adj_list, total_nodes);
-    println!("Final number of components: {}", final_components);
+    #[test]
+    fn kd_tree_matches_brute_force() {
+        // Small coordinate range so there are plenty of duplicate distances
+        let mut seed = 12345_u64;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i64 % 20 - 10
+        };
+        let points: Vec<Point> = (0..150)
+            .map(|_| Point {
+                x: next(),
+                y: next(),
+                z: next(),
+            })
+            .collect();
+        assert_eq!(connections(&points), brute_force(&points));
+    }
+
+    fn example_points() -> Vec<Point> {
+        let contents = fs::read_to_string("test.txt").expect("Failed to read test.txt");
+        contents
+            .lines()
+            .map(|s| {
+                let coords: Vec<i64> = s.split(',').map(|n| n.parse().unwrap()).collect();
+                Point::try_from(coords.as_slice()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exported_forest_matches_kruskal() {
+        let points = example_points();
+        let forest = mst::spanning_forest(points.len(), ClosestPairs::new(&points), None);
+
+        // Kruskal again over the brute-force pairs
+        let mut circuits = DisjointSet::new(points.len());
+        let kept: Vec<Connection> = brute_force(&points)
+            .into_iter()
+            .filter(|&(_, i, j)| circuits.union(i, j))
+            .collect();
+        let total: f64 = kept.iter().map(|&(d, _, _)| (d as f64).sqrt()).sum();
+
+        assert_eq!(forest.edges, kept);
+        assert_eq!(forest.edges.len(), points.len() - 1);
+        let last = forest.steps.last().unwrap();
+        assert_eq!(last.components, 1);
+        assert!((last.total_weight - total).abs() < 1e-9);
+        let (_, closing) = closing_connection(points.len(), ClosestPairs::new(&points)).unwrap();
+        assert_eq!(forest.edges.last(), Some(&closing));
+
+        assert_eq!(forest.to_edge_list().lines().count(), kept.len());
+        let json = forest.to_json(&points);
+        assert!(json.starts_with('{'));
+        assert_eq!(json.matches("\"source\"").count(), kept.len());
+        let dot = forest.to_dot(&points);
+        assert!(dot.starts_with("graph circuits {"));
+        assert_eq!(dot.matches(" -- ").count(), kept.len());
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let points = example_points();
+        let sizes = circuit_sizes_after(10, points.len(), ClosestPairs::new(&points));
+        assert_eq!(sizes.iter().take(3).product::<usize>(), 40);
+        let (_, (_, i, j)) = closing_connection(points.len(), ClosestPairs::new(&points)).unwrap();
+        assert_eq!(points[i].x * points[j].x, 25272);
+    }
 }
@@ -0,0 +1,124 @@
+use crate::dsu::DisjointSet;
+use crate::{Connection, Point};
+use std::fmt::Write;
+
+/// Circuit state right after an edge was accepted into the forest.
+#[derive(Debug, Clone, Copy)]
+pub struct Step {
+    /// How many pairs had been looked at, including this one.
+    pub considered: usize,
+    pub components: usize,
+    pub total_weight: f64,
+    pub longest_edge: f64,
+}
+
+/// The edges Kruskal keeps, i.e. the ones that joined two different circuits.
+#[derive(Debug, Clone)]
+pub struct SpanningForest {
+    pub edges: Vec<Connection>,
+    pub steps: Vec<Step>,
+}
+
+fn weight(squared: i128) -> f64 {
+    (squared as f64).sqrt()
+}
+
+/// Runs Kruskal over `connections` (already in increasing order). Stops after
+/// `limit` pairs if given, which gives the forest part 1 looks at, otherwise
+/// once everything is connected, which gives the minimum spanning tree.
+pub fn spanning_forest(
+    total_nodes: usize,
+    connections: impl IntoIterator<Item = Connection>,
+    limit: Option<usize>,
+) -> SpanningForest {
+    let mut circuits = DisjointSet::new(total_nodes);
+    let mut forest = SpanningForest {
+        edges: Vec::new(),
+        steps: Vec::new(),
+    };
+    let mut total_weight = 0.0;
+    let mut longest_edge: f64 = 0.0;
+    for (considered, (dist, i, j)) in connections
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .enumerate()
+    {
+        if circuits.components() == 1 {
+            break;
+        }
+        if !circuits.union(i, j) {
+            continue;
+        }
+        total_weight += weight(dist);
+        longest_edge = longest_edge.max(weight(dist));
+        forest.edges.push((dist, i, j));
+        forest.steps.push(Step {
+            considered: considered + 1,
+            components: circuits.components(),
+            total_weight,
+            longest_edge,
+        });
+    }
+    forest
+}
+
+impl SpanningForest {
+    /// One `i j weight` line per edge.
+    pub fn to_edge_list(&self) -> String {
+        let mut out = String::new();
+        for &(dist, i, j) in &self.edges {
+            writeln!(out, "{} {} {:.3}", i, j, weight(dist)).unwrap();
+        }
+        out
+    }
+
+    /// Undirected GraphViz graph with nodes pinned at their x/y coordinates
+    /// (z goes in the label) and edges labelled with their length.
+    pub fn to_dot(&self, points: &[Point]) -> String {
+        let mut out = String::from("graph circuits {\n  node [shape=point];\n");
+        for (i, p) in points.iter().enumerate() {
+            writeln!(
+                out,
+                "  {} [pos=\"{},{}\", label=\"{},{},{}\"];",
+                i, p.x, p.y, p.x, p.y, p.z
+            )
+            .unwrap();
+        }
+        for &(dist, i, j) in &self.edges {
+            writeln!(out, "  {} -- {} [label=\"{:.3}\"];", i, j, weight(dist)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self, points: &[Point]) -> String {
+        let nodes: Vec<String> = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                format!(
+                    "    {{\"id\": {}, \"x\": {}, \"y\": {}, \"z\": {}}}",
+                    i, p.x, p.y, p.z
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|&(dist, i, j)| {
+                format!(
+                    "    {{\"source\": {}, \"target\": {}, \"squared_distance\": {}, \"weight\": {}}}",
+                    i,
+                    j,
+                    dist,
+                    weight(dist)
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{Point2d, Rectangle};
+use std::collections::VecDeque;
+
+/// One axis of the compressed grid. Every coordinate used by a red tile gets
+/// its own cell, and so does every gap between two of them, standing in for
+/// all the tiles in the gap. Gaps between neighbouring coordinates hold no
+/// tiles at all, but their cells still let the outside through between two
+/// boundary lines running side by side. There is an extra gap cell at each
+/// end so the outside is connected all the way round.
+struct Axis {
+    coords: Vec<i32>,
+    /// Compressed cell of each entry in `coords`
+    cells: Vec<usize>,
+    /// First tile coordinate and number of tiles in each compressed cell
+    starts: Vec<i64>,
+    widths: Vec<i64>,
+}
+
+impl Axis {
+    fn new(mut coords: Vec<i32>) -> Self {
+        coords.sort_unstable();
+        coords.dedup();
+        let mut cells = Vec::with_capacity(coords.len());
+        let mut starts = vec![coords[0] as i64 - 1];
+        let mut widths = vec![1];
+        for (k, &c) in coords.iter().enumerate() {
+            if k > 0 {
+                starts.push(coords[k - 1] as i64 + 1);
+                widths.push(c as i64 - coords[k - 1] as i64 - 1);
+            }
+            cells.push(starts.len());
+            starts.push(c as i64);
+            widths.push(1);
+        }
+        starts.push(*coords.last().unwrap() as i64 + 1);
+        widths.push(1);
+        Axis {
+            coords,
+            cells,
+            starts,
+            widths,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Cell index of a coordinate that belongs to a red tile.
+    fn index(&self, value: i32) -> usize {
+        let k = self
+            .coords
+            .binary_search(&value)
+            .expect("coordinate of a red tile");
+        self.cells[k]
+    }
+}
+
+/// One or more red-tile loops rasterised on a coordinate-compressed grid,
+/// with a 2D prefix sum over the cells outside all of them. Whether a
+/// rectangle only covers red and green tiles then comes down to four lookups.
+pub struct CompressedGrid {
+    xs: Axis,
+    ys: Axis,
+    /// `inside[y][x]` is true if the cell's tiles are red or green
+    inside: Vec<Vec<bool>>,
+    /// `outside[y][x]` counts outside tiles in the half-open block of cells
+    /// `[0, y) × [0, x)`, so empty gap cells add nothing
+    outside: Vec<Vec<i64>>,
+}
+
+impl CompressedGrid {
+    /// Tiles count as green if they are on or inside any of the loops.
+    pub fn new(loops: &[Vec<Point2d>]) -> Self {
+        let xs = Axis::new(loops.iter().flatten().map(|p| p.x).collect());
+        let ys = Axis::new(loops.iter().flatten().map(|p| p.y).collect());
+        let (width, height) = (xs.len(), ys.len());
+
+        // Each loop is flooded on its own, so overlapping loops that happen to
+        // enclose a hole between them don't fill it in
+        let mut inside = vec![vec![false; width]; height];
+        for red_tiles in loops {
+            let outside = flood_outside(red_tiles, &xs, &ys);
+            for (inside_row, outside_row) in inside.iter_mut().zip(&outside) {
+                for (cell, &out) in inside_row.iter_mut().zip(outside_row) {
+                    *cell |= !out;
+                }
+            }
+        }
+
+        let mut outside = vec![vec![0_i64; width + 1]; height + 1];
+        for y in 0..height {
+            for x in 0..width {
+                let tiles = if inside[y][x] {
+                    0
+                } else {
+                    xs.widths[x] * ys.widths[y]
+                };
+                outside[y + 1][x + 1] =
+                    outside[y][x + 1] + outside[y + 1][x] - outside[y][x] + tiles;
+            }
+        }
+
+        CompressedGrid {
+            xs,
+            ys,
+            inside,
+            outside,
+        }
+    }
+
+    fn cell(&self, p: &Point2d) -> (usize, usize) {
+        (self.xs.index(p.x), self.ys.index(p.y))
+    }
+
+    /// True if the rectangle with opposite corners `a` and `b` (both red
+    /// tiles) contains only red or green tiles.
+    pub fn contains_rectangle(&self, a: &Point2d, b: &Point2d) -> bool {
+        let (ax, ay) = self.cell(a);
+        let (bx, by) = self.cell(b);
+        let (x0, x1) = (ax.min(bx), ax.max(bx) + 1);
+        let (y0, y1) = (ay.min(by), ay.max(by) + 1);
+        let outside = self.outside[y1][x1] + self.outside[y0][x0]
+            - self.outside[y0][x1]
+            - self.outside[y1][x0];
+        outside == 0
+    }
+
+    /// Largest rectangle of red and green tiles with any corners at all.
+    ///
+    /// A best rectangle can always be grown until each side runs along a
+    /// compressed cell border, so this is the largest-rectangle-in-a-histogram
+    /// sweep over compressed rows, with every bar as wide and tall as the
+    /// tiles its cells stand for.
+    pub fn largest_rectangle(&self) -> Option<Rectangle> {
+        // Empty gap cells hold no tiles, so they neither hold up a bar nor
+        // cut one off
+        let columns: Vec<usize> = (0..self.xs.len())
+            .filter(|&x| self.xs.widths[x] > 0)
+            .collect();
+        let mut heights = vec![0_i64; columns.len()];
+        let mut best: Option<Rectangle> = None;
+        for (y, row) in self.inside.iter().enumerate() {
+            if self.ys.widths[y] == 0 {
+                continue;
+            }
+            for (height, &x) in heights.iter_mut().zip(&columns) {
+                *height = if row[x] {
+                    *height + self.ys.widths[y]
+                } else {
+                    0
+                };
+            }
+            let bottom = self.ys.starts[y] + self.ys.widths[y] - 1;
+
+            // Bars still waiting for a lower one to close them: (first bar, height)
+            let mut stack: Vec<(usize, i64)> = Vec::new();
+            for k in 0..=columns.len() {
+                let h = heights.get(k).copied().unwrap_or(0);
+                let mut first = k;
+                while let Some(&(start, bar)) = stack.last() {
+                    if bar < h {
+                        break;
+                    }
+                    stack.pop();
+                    first = start;
+                    let (left_cell, right_cell) = (columns[start], columns[k - 1]);
+                    let left = self.xs.starts[left_cell];
+                    let right = self.xs.starts[right_cell] + self.xs.widths[right_cell] - 1;
+                    let area = ((right - left + 1) * bar) as u64;
+                    if bar > 0 && best.is_none_or(|rect| area > rect.area) {
+                        best = Some(Rectangle {
+                            a: Point2d {
+                                x: left as i32,
+                                y: (bottom - bar + 1) as i32,
+                            },
+                            b: Point2d {
+                                x: right as i32,
+                                y: bottom as i32,
+                            },
+                            area,
+                        });
+                    }
+                }
+                stack.push((first, h));
+            }
+        }
+        best
+    }
+}
+
+/// Cells outside one loop, found by flooding from the padded corner.
+fn flood_outside(red_tiles: &[Point2d], xs: &Axis, ys: &Axis) -> Vec<Vec<bool>> {
+    let (width, height) = (xs.len(), ys.len());
+
+    // Draw the loop itself
+    let mut boundary = vec![vec![false; width]; height];
+    for i in 0..red_tiles.len() {
+        let start = red_tiles[i];
+        let end = red_tiles[(i + 1) % red_tiles.len()];
+        let (sx, ex) = (xs.index(start.x), xs.index(end.x));
+        let (sy, ey) = (ys.index(start.y), ys.index(end.y));
+        for row in &mut boundary[sy.min(ey)..=sy.max(ey)] {
+            for cell in &mut row[sx.min(ex)..=sx.max(ex)] {
+                *cell = true;
+            }
+        }
+    }
+
+    let mut is_outside = vec![vec![false; width]; height];
+    let mut queue = VecDeque::from([(0_usize, 0_usize)]);
+    is_outside[0][0] = true;
+    while let Some((x, y)) = queue.pop_front() {
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < width && ny < height && !boundary[ny][nx] && !is_outside[ny][nx] {
+                is_outside[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    is_outside
+}
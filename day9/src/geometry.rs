@@ -0,0 +1,251 @@
+use crate::Point2d;
+
+/// An axis-aligned polygon edge, stored with its constant coordinate and the
+/// closed range it spans along the other axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Horizontal { y: i64, x0: i64, x1: i64 },
+    Vertical { x: i64, y0: i64, y1: i64 },
+}
+
+impl Edge {
+    fn new(a: Point2d, b: Point2d) -> Option<Edge> {
+        let (ax, ay, bx, by) = (a.x as i64, a.y as i64, b.x as i64, b.y as i64);
+        if ay == by && ax != bx {
+            Some(Edge::Horizontal {
+                y: ay,
+                x0: ax.min(bx),
+                x1: ax.max(bx),
+            })
+        } else if ax == bx && ay != by {
+            Some(Edge::Vertical {
+                x: ax,
+                y0: ay.min(by),
+                y1: ay.max(by),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn is_vertical(&self) -> bool {
+        matches!(self, Edge::Vertical { .. })
+    }
+
+    /// Closed segments share at least one point.
+    fn touches(&self, other: &Edge) -> bool {
+        match (*self, *other) {
+            (
+                Edge::Horizontal { y, x0, x1 },
+                Edge::Horizontal {
+                    y: oy,
+                    x0: ox0,
+                    x1: ox1,
+                },
+            ) => y == oy && x0 <= ox1 && ox0 <= x1,
+            (
+                Edge::Vertical { x, y0, y1 },
+                Edge::Vertical {
+                    x: ox,
+                    y0: oy0,
+                    y1: oy1,
+                },
+            ) => x == ox && y0 <= oy1 && oy0 <= y1,
+            (Edge::Horizontal { y, x0, x1 }, Edge::Vertical { x, y0, y1 })
+            | (Edge::Vertical { x, y0, y1 }, Edge::Horizontal { y, x0, x1 }) => {
+                x0 <= x && x <= x1 && y0 <= y && y <= y1
+            }
+        }
+    }
+
+    /// The edge passes through the open rectangle `(x0, x1) × (y0, y1)`.
+    fn crosses_interior(&self, x0: i64, x1: i64, y0: i64, y1: i64) -> bool {
+        match *self {
+            Edge::Horizontal {
+                y,
+                x0: ex0,
+                x1: ex1,
+            } => y0 < y && y < y1 && ex0 < x1 && x0 < ex1,
+            Edge::Vertical {
+                x,
+                y0: ey0,
+                y1: ey1,
+            } => x0 < x && x < x1 && ey0 < y1 && y0 < ey1,
+        }
+    }
+}
+
+/// A simple polygon whose edges are all horizontal or vertical, such as the
+/// loop of red tiles. Tiles are the polygon's integer points, so containment
+/// is boundary inclusive.
+#[derive(Debug, Clone)]
+pub struct RectilinearPolygon {
+    vertices: Vec<Point2d>,
+    edges: Vec<Edge>,
+}
+
+impl RectilinearPolygon {
+    /// Checks that consecutive vertices (wrapping round) form axis-aligned
+    /// edges that alternate between horizontal and vertical, and that edges
+    /// only meet their two neighbours, each at the shared corner.
+    pub fn new(vertices: &[Point2d]) -> Result<Self, String> {
+        let n = vertices.len();
+        if n < 4 || !n.is_multiple_of(2) {
+            return Err(format!(
+                "a rectilinear polygon needs an even number of at least 4 corners, got {}",
+                n
+            ));
+        }
+        let mut edges = Vec::with_capacity(n);
+        for i in 0..n {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            let edge = Edge::new(a, b).ok_or_else(|| {
+                format!(
+                    "edge {} from {},{} to {},{} is not a horizontal or vertical line",
+                    i, a.x, a.y, b.x, b.y
+                )
+            })?;
+            edges.push(edge);
+        }
+        for i in 0..n {
+            if edges[i].is_vertical() == edges[(i + 1) % n].is_vertical() {
+                return Err(format!(
+                    "edges {} and {} are collinear at corner {},{}",
+                    i,
+                    (i + 1) % n,
+                    vertices[(i + 1) % n].x,
+                    vertices[(i + 1) % n].y
+                ));
+            }
+        }
+        for i in 0..n {
+            // Neighbours are perpendicular, so they only meet at the corner
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                if edges[i].touches(&edges[j]) {
+                    return Err(format!("edges {} and {} intersect", i, j));
+                }
+            }
+        }
+        Ok(RectilinearPolygon {
+            vertices: vertices.to_vec(),
+            edges,
+        })
+    }
+
+    pub fn vertices(&self) -> &[Point2d] {
+        &self.vertices
+    }
+
+    /// Boundary-inclusive point test on doubled coordinates, so half-integer
+    /// points such as rectangle centres stay exact.
+    fn contains_doubled(&self, px: i64, py: i64) -> bool {
+        let on_boundary = self.edges.iter().any(|edge| match *edge {
+            Edge::Horizontal { y, x0, x1 } => py == 2 * y && 2 * x0 <= px && px <= 2 * x1,
+            Edge::Vertical { x, y0, y1 } => px == 2 * x && 2 * y0 <= py && py <= 2 * y1,
+        });
+        if on_boundary {
+            return true;
+        }
+        // Cast a ray towards +x; half-open spans count each vertex once
+        let crossings = self
+            .edges
+            .iter()
+            .filter(|edge| match **edge {
+                Edge::Vertical { x, y0, y1 } => 2 * x > px && 2 * y0 <= py && py < 2 * y1,
+                Edge::Horizontal { .. } => false,
+            })
+            .count();
+        crossings % 2 == 1
+    }
+
+    /// A tile is red or green if its point lies inside or on the loop.
+    pub fn contains_point(&self, p: &Point2d) -> bool {
+        self.contains_doubled(2 * p.x as i64, 2 * p.y as i64)
+    }
+
+    /// True if the axis-aligned rectangle with opposite corners `a` and `b`
+    /// lies inside the polygon, boundary included.
+    ///
+    /// No edge may pass through the open interior of the rectangle. Then the
+    /// interior is either completely inside or completely outside, and the
+    /// centre decides which. Flat rectangles have no interior and are checked
+    /// as a segment instead.
+    ///
+    /// This is containment in the plane. It matches "every tile is red or
+    /// green" except where two edges run on neighbouring lines with the
+    /// outside in between: that sliver holds no tiles but still fails here.
+    pub fn contains_rectangle(&self, a: &Point2d, b: &Point2d) -> bool {
+        let (x0, x1) = ((a.x.min(b.x)) as i64, (a.x.max(b.x)) as i64);
+        let (y0, y1) = ((a.y.min(b.y)) as i64, (a.y.max(b.y)) as i64);
+        if x0 == x1 || y0 == y1 {
+            return self.contains_segment(x0, x1, y0, y1);
+        }
+        if self
+            .edges
+            .iter()
+            .any(|edge| edge.crosses_interior(x0, x1, y0, y1))
+        {
+            return false;
+        }
+        self.contains_doubled(x0 + x1, y0 + y1)
+    }
+
+    /// A horizontal or vertical segment (or a single point) is inside if its
+    /// ends, every place an edge meets it, and every stretch in between are.
+    fn contains_segment(&self, x0: i64, x1: i64, y0: i64, y1: i64) -> bool {
+        let horizontal = y0 == y1;
+        let (lo, hi) = if horizontal { (x0, x1) } else { (y0, y1) };
+        let mut stops = vec![lo, hi];
+        for edge in &self.edges {
+            match (*edge, horizontal) {
+                (
+                    Edge::Vertical {
+                        x,
+                        y0: ey0,
+                        y1: ey1,
+                    },
+                    true,
+                ) if ey0 <= y0 && y0 <= ey1 => stops.push(x),
+                (
+                    Edge::Horizontal {
+                        y,
+                        x0: ex0,
+                        x1: ex1,
+                    },
+                    false,
+                ) if ex0 <= x0 && x0 <= ex1 => stops.push(y),
+                (
+                    Edge::Horizontal {
+                        x0: ex0,
+                        x1: ex1,
+                        y,
+                    },
+                    true,
+                ) if y == y0 => stops.extend([ex0, ex1]),
+                (
+                    Edge::Vertical {
+                        y0: ey0,
+                        y1: ey1,
+                        x,
+                    },
+                    false,
+                ) if x == x0 => stops.extend([ey0, ey1]),
+                _ => {}
+            }
+        }
+        stops.retain(|&s| lo <= s && s <= hi);
+        stops.sort_unstable();
+        stops.dedup();
+        let at = |along: i64| {
+            if horizontal {
+                self.contains_doubled(along, 2 * y0)
+            } else {
+                self.contains_doubled(2 * x0, along)
+            }
+        };
+        stops.iter().all(|&s| at(2 * s)) && stops.windows(2).all(|w| at(w[0] + w[1]))
+    }
+}
@@ -0,0 +1,249 @@
+use crate::compressed::CompressedGrid;
+use crate::{Point2d, Rectangle, best_rectangles};
+
+/// Red tiles of all loops, each tile once.
+fn red_tiles(loops: &[Vec<Point2d>]) -> Vec<Point2d> {
+    let mut red: Vec<Point2d> = loops.iter().flatten().copied().collect();
+    red.sort_unstable_by_key(|p| (p.x, p.y));
+    red.dedup();
+    red
+}
+
+/// Largest rectangle of red and green tiles, with corners on any tile.
+pub fn largest_inside_rectangle(loops: &[Vec<Point2d>]) -> Option<Rectangle> {
+    CompressedGrid::new(loops).largest_rectangle()
+}
+
+/// Part 2 over several loops: the largest rectangle with red tiles of any
+/// loop as corners that only covers tiles on or inside one of the loops.
+pub fn best_red_rectangle(loops: &[Vec<Point2d>]) -> Option<Rectangle> {
+    let grid = CompressedGrid::new(loops);
+    best_rectangles(&red_tiles(loops), |a, b| grid.contains_rectangle(a, b)).1
+}
+
+/// How many pairs of red tiles span a rectangle of only red and green tiles.
+pub fn count_valid_rectangles(loops: &[Vec<Point2d>]) -> usize {
+    let grid = CompressedGrid::new(loops);
+    let red = red_tiles(loops);
+    (0..red.len())
+        .map(|i| {
+            (i + 1..red.len())
+                .filter(|&j| grid.contains_rectangle(&red[i], &red[j]))
+                .count()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::RectilinearPolygon;
+    use std::collections::HashSet;
+
+    fn shape(corners: &[(i32, i32)]) -> Vec<Point2d> {
+        corners.iter().map(|&(x, y)| Point2d { x, y }).collect()
+    }
+
+    fn shapes() -> Vec<Vec<Vec<Point2d>>> {
+        vec![
+            // The puzzle example
+            vec![shape(&[
+                (7, 1),
+                (11, 1),
+                (11, 7),
+                (9, 7),
+                (9, 5),
+                (2, 5),
+                (2, 3),
+                (7, 3),
+            ])],
+            // A notch between neighbouring rows that holds no tiles
+            vec![shape(&[
+                (2, 7),
+                (4, 7),
+                (4, 4),
+                (8, 4),
+                (8, 6),
+                (7, 6),
+                (7, 7),
+                (8, 7),
+                (8, 8),
+                (2, 8),
+            ])],
+            // U shape, the best free rectangle has no red corners
+            vec![shape(&[
+                (0, 0),
+                (9, 0),
+                (9, 6),
+                (6, 6),
+                (6, 2),
+                (3, 2),
+                (3, 6),
+                (0, 6),
+            ])],
+            // Plus sign
+            vec![shape(&[
+                (3, 0),
+                (5, 0),
+                (5, 3),
+                (8, 3),
+                (8, 5),
+                (5, 5),
+                (5, 8),
+                (3, 8),
+                (3, 5),
+                (0, 5),
+                (0, 3),
+                (3, 3),
+            ])],
+            // A pocket whose only way out is a slit between the neighbouring
+            // columns 5 and 6, then the same turned on its side
+            vec![shape(&[
+                (0, 0),
+                (5, 0),
+                (5, 3),
+                (3, 3),
+                (3, 7),
+                (7, 7),
+                (7, 3),
+                (6, 3),
+                (6, 0),
+                (10, 0),
+                (10, 10),
+                (0, 10),
+            ])],
+            vec![shape(&[
+                (0, 0),
+                (0, 5),
+                (3, 5),
+                (3, 3),
+                (7, 3),
+                (7, 7),
+                (3, 7),
+                (3, 6),
+                (0, 6),
+                (0, 10),
+                (10, 10),
+                (10, 0),
+            ])],
+            // Two loops apart from each other
+            vec![
+                shape(&[(0, 0), (3, 0), (3, 2), (0, 2)]),
+                shape(&[(6, 1), (9, 1), (9, 7), (6, 7)]),
+            ],
+            // Two L shapes that overlap and leave a hole between them
+            vec![
+                shape(&[(0, 0), (6, 0), (6, 2), (2, 2), (2, 6), (0, 6)]),
+                shape(&[(6, 0), (8, 0), (8, 8), (0, 8), (0, 5), (6, 5)]),
+            ],
+        ]
+    }
+
+    /// Every red or green tile, found one point at a time.
+    fn tiles(loops: &[Vec<Point2d>]) -> HashSet<(i32, i32)> {
+        let polygons: Vec<RectilinearPolygon> = loops
+            .iter()
+            .map(|l| RectilinearPolygon::new(l).unwrap())
+            .collect();
+        let (min_x, max_x, min_y, max_y) = bounds(loops);
+        let mut tiles = HashSet::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if polygons.iter().any(|p| p.contains_point(&Point2d { x, y })) {
+                    tiles.insert((x, y));
+                }
+            }
+        }
+        tiles
+    }
+
+    fn bounds(loops: &[Vec<Point2d>]) -> (i32, i32, i32, i32) {
+        let all = || loops.iter().flatten();
+        (
+            all().map(|p| p.x).min().unwrap(),
+            all().map(|p| p.x).max().unwrap(),
+            all().map(|p| p.y).min().unwrap(),
+            all().map(|p| p.y).max().unwrap(),
+        )
+    }
+
+    fn covered(tiles: &HashSet<(i32, i32)>, a: (i32, i32), b: (i32, i32)) -> bool {
+        (a.0.min(b.0)..=a.0.max(b.0))
+            .all(|x| (a.1.min(b.1)..=a.1.max(b.1)).all(|y| tiles.contains(&(x, y))))
+    }
+
+    fn area(a: (i32, i32), b: (i32, i32)) -> u64 {
+        (a.0.abs_diff(b.0) as u64 + 1) * (a.1.abs_diff(b.1) as u64 + 1)
+    }
+
+    fn red_pairs(loops: &[Vec<Point2d>]) -> Vec<((i32, i32), (i32, i32))> {
+        let red: Vec<(i32, i32)> = red_tiles(loops).iter().map(|p| (p.x, p.y)).collect();
+        let mut pairs = Vec::new();
+        for i in 0..red.len() {
+            for j in i + 1..red.len() {
+                pairs.push((red[i], red[j]));
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn largest_inside_rectangle_matches_brute_force() {
+        for loops in shapes() {
+            let tiles = tiles(&loops);
+            let (min_x, max_x, min_y, max_y) = bounds(&loops);
+            let mut expected = 0;
+            for x0 in min_x..=max_x {
+                for x1 in x0..=max_x {
+                    for y0 in min_y..=max_y {
+                        for y1 in y0..=max_y {
+                            if covered(&tiles, (x0, y0), (x1, y1)) {
+                                expected = expected.max(area((x0, y0), (x1, y1)));
+                            }
+                        }
+                    }
+                }
+            }
+            let rect = largest_inside_rectangle(&loops).unwrap();
+            assert_eq!(rect.area, expected, "{:?}", loops);
+            assert!(covered(&tiles, (rect.a.x, rect.a.y), (rect.b.x, rect.b.y)));
+            assert_eq!(area((rect.a.x, rect.a.y), (rect.b.x, rect.b.y)), rect.area);
+        }
+    }
+
+    #[test]
+    fn best_red_rectangle_matches_brute_force() {
+        for loops in shapes() {
+            let tiles = tiles(&loops);
+            let expected = red_pairs(&loops)
+                .into_iter()
+                .filter(|&(a, b)| covered(&tiles, a, b))
+                .map(|(a, b)| area(a, b))
+                .max();
+            assert_eq!(
+                best_red_rectangle(&loops).map(|r| r.area),
+                expected,
+                "{:?}",
+                loops
+            );
+        }
+    }
+
+    #[test]
+    fn count_valid_rectangles_matches_brute_force() {
+        for loops in shapes() {
+            let tiles = tiles(&loops);
+            let expected = red_pairs(&loops)
+                .into_iter()
+                .filter(|&(a, b)| covered(&tiles, a, b))
+                .count();
+            assert_eq!(count_valid_rectangles(&loops), expected, "{:?}", loops);
+        }
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let loops = &shapes()[0];
+        assert_eq!(best_red_rectangle(loops).unwrap().area, 24);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::geometry::RectilinearPolygon;
+use crate::{Point2d, Rectangle};
+use std::fmt::Write;
+
+const PART1_COLOUR: &str = "#1f77b4";
+const PART2_COLOUR: &str = "#ff7f0e";
+/// Above this many corners the SVG only labels the rectangle corners.
+const MAX_LABELLED_CORNERS: usize = 40;
+const MAX_ASCII_COLS: i64 = 100;
+const MAX_ASCII_ROWS: i64 = 60;
+
+struct Bounds {
+    min_x: i64,
+    max_x: i64,
+    min_y: i64,
+    max_y: i64,
+}
+
+fn bounds(polygon: &RectilinearPolygon) -> Bounds {
+    let vertices = polygon.vertices();
+    Bounds {
+        min_x: vertices.iter().map(|p| p.x as i64).min().unwrap(),
+        max_x: vertices.iter().map(|p| p.x as i64).max().unwrap(),
+        min_y: vertices.iter().map(|p| p.y as i64).min().unwrap(),
+        max_y: vertices.iter().map(|p| p.y as i64).max().unwrap(),
+    }
+}
+
+fn rect_span(rect: &Rectangle) -> (i64, i64, i64, i64) {
+    let (ax, ay, bx, by) = (
+        rect.a.x as i64,
+        rect.a.y as i64,
+        rect.b.x as i64,
+        rect.b.y as i64,
+    );
+    (ax.min(bx), ax.max(bx), ay.min(by), ay.max(by))
+}
+
+/// SVG of the loop filled green with the red tiles on its corners and the two
+/// best rectangles on top. Tiles are unit squares centred on their point.
+pub fn svg(
+    polygon: &RectilinearPolygon,
+    part1: Option<&Rectangle>,
+    part2: Option<&Rectangle>,
+) -> String {
+    let b = bounds(polygon);
+    let extent = (b.max_x - b.min_x).max(b.max_y - b.min_y).max(1) as f64;
+    let margin = extent / 10.0;
+    let font = extent / 50.0;
+    let dot = (extent / 300.0).max(0.5);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" font-family="monospace" font-size="{}">"##,
+        b.min_x as f64 - margin,
+        b.min_y as f64 - margin,
+        (b.max_x - b.min_x) as f64 + 2.0 * margin,
+        (b.max_y - b.min_y) as f64 + 2.0 * margin,
+        font
+    )
+    .unwrap();
+
+    let points: Vec<String> = polygon
+        .vertices()
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect();
+    writeln!(
+        out,
+        r##"  <polygon points="{}" fill="#2ca02c" fill-opacity="0.5" stroke="#2ca02c" stroke-width="1" vector-effect="non-scaling-stroke"/>"##,
+        points.join(" ")
+    )
+    .unwrap();
+
+    for (part, rect, colour) in [(1, part1, PART1_COLOUR), (2, part2, PART2_COLOUR)] {
+        let Some(rect) = rect else { continue };
+        let (x0, x1, y0, y1) = rect_span(rect);
+        writeln!(
+            out,
+            r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="0.25" stroke="{}" stroke-width="2" vector-effect="non-scaling-stroke"/>"##,
+            x0 as f64 - 0.5,
+            y0 as f64 - 0.5,
+            x1 - x0 + 1,
+            y1 - y0 + 1,
+            colour,
+            colour
+        )
+        .unwrap();
+        for corner in [rect.a, rect.b] {
+            writeln!(
+                out,
+                r##"  <text x="{}" y="{}" fill="{}">{},{}</text>"##,
+                corner.x,
+                corner.y as f64 - font / 2.0,
+                colour,
+                corner.x,
+                corner.y
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            r##"  <text x="{}" y="{}" fill="{}">part {}: area {}</text>"##,
+            b.min_x as f64 - margin / 2.0,
+            b.min_y as f64 - margin / 2.0 + (part - 1) as f64 * font * 1.5,
+            colour,
+            part,
+            rect.area
+        )
+        .unwrap();
+    }
+
+    let label_corners = polygon.vertices().len() <= MAX_LABELLED_CORNERS;
+    for p in polygon.vertices() {
+        writeln!(
+            out,
+            r##"  <circle cx="{}" cy="{}" r="{}" fill="red"/>"##,
+            p.x, p.y, dot
+        )
+        .unwrap();
+        if label_corners {
+            writeln!(
+                out,
+                r##"  <text x="{}" y="{}" fill="#555">{},{}</text>"##,
+                p.x as f64 + dot,
+                p.y as f64 + font,
+                p.x,
+                p.y
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Text picture using the puzzle's own `#` (red) and `X` (green). Tiles under
+/// the part 2 and part 1 rectangles show `2` and `1`, part 2 on top. Larger
+/// inputs are scaled down so each character stands for a block of tiles.
+pub fn ascii(
+    polygon: &RectilinearPolygon,
+    part1: Option<&Rectangle>,
+    part2: Option<&Rectangle>,
+) -> String {
+    let b = bounds(polygon);
+    let (min_x, min_y) = (b.min_x - 1, b.min_y - 1);
+    let (width, height) = (b.max_x - min_x + 2, b.max_y - min_y + 2);
+    let scale = ((width + MAX_ASCII_COLS - 1) / MAX_ASCII_COLS)
+        .max((height + MAX_ASCII_ROWS - 1) / MAX_ASCII_ROWS)
+        .max(1);
+    let red: Vec<(i64, i64)> = polygon
+        .vertices()
+        .iter()
+        .map(|p| (p.x as i64, p.y as i64))
+        .collect();
+    let overlaps = |rect: Option<&Rectangle>, x: i64, y: i64| {
+        rect.is_some_and(|rect| {
+            let (x0, x1, y0, y1) = rect_span(rect);
+            x0 < x + scale && x <= x1 && y0 < y + scale && y <= y1
+        })
+    };
+
+    let label_width = (b.max_y.to_string().len()).max(min_y.to_string().len());
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>w$} x from {} to {}, {} tile(s) per character",
+        "",
+        min_x,
+        min_x + (width - 1) / scale * scale,
+        scale,
+        w = label_width
+    )
+    .unwrap();
+    for y in (min_y..min_y + height).step_by(scale as usize) {
+        write!(out, "{:>w$} ", y, w = label_width).unwrap();
+        for x in (min_x..min_x + width).step_by(scale as usize) {
+            let is_red = red
+                .iter()
+                .any(|&(rx, ry)| x <= rx && rx < x + scale && y <= ry && ry < y + scale);
+            let glyph = if is_red {
+                '#'
+            } else if overlaps(part2, x, y) {
+                '2'
+            } else if overlaps(part1, x, y) {
+                '1'
+            } else if polygon.contains_point(&Point2d {
+                x: x as i32,
+                y: y as i32,
+            }) {
+                'X'
+            } else {
+                '.'
+            };
+            out.push(glyph);
+        }
+        out.push('\n');
+    }
+    out
+}